@@ -0,0 +1,305 @@
+//! Decoders for the data section of a `.raw`-File
+//!
+//! Every decoder appends the values of each variable to `reals` and `imags`. Real values get
+//! an imaginary part of zero, so the storage looks the same for every kind of file.
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::str::FromStr;
+
+use encoding::Encoding;
+use error::ParseError;
+use header::{RawHeader, Simulator};
+
+
+/// Returns the number of bytes of one value of the variable with the given index.
+///
+/// Complex values are stored as two doubles (real and imaginary part). Real values are
+/// stored as floats, except for the sweep variable (e.g. the time), which is stored as a double.
+/// `.op` and `.tf` plots have no sweep variable, so all of their values are floats.
+/// With the flag `double` (LTspice) and in files of ngspice all real values are doubles.
+pub fn sample_size(header: &RawHeader,var_index: usize) -> usize {
+    if header.flags.complex {
+        16
+    } else if header.flags.double || header.simulator == Simulator::Ngspice ||
+              (var_index == 0 && header.plot_kind.has_sweep_variable()) {
+        8
+    } else {
+        4
+    }
+}
+
+/// Returns the number of bytes of one point (one value of every variable)
+pub fn point_size(header: &RawHeader) -> usize {
+    (0..header.nr_of_variables).map(|var_index| sample_size(header,var_index)).sum()
+}
+
+/// Reads one value of the variable with the given index from the start of the buffer
+pub fn read_sample(buf: &[u8],header: &RawHeader,var_index: usize) -> (f64,f64) {
+    match sample_size(header,var_index) {
+        16 => (read_f64(&buf[0..8],header),read_f64(&buf[8..16],header)),
+        8 => (read_f64(&buf[0..8],header),0.0),
+        _ => (read_f32(&buf[0..4],header) as f64,0.0)
+    }
+}
+
+fn read_f64(buf: &[u8],header: &RawHeader) -> f64 {
+    f64_from_bytes(buf,header.big_endian)
+}
+
+fn read_f32(buf: &[u8],header: &RawHeader) -> f32 {
+    // The values are copied, because floats in the file are not aligned
+    let mut bytes = [0u8;4];
+    bytes.copy_from_slice(&buf[..4]);
+    if header.big_endian {
+        f32::from_be_bytes(bytes)
+    } else {
+        f32::from_le_bytes(bytes)
+    }
+}
+
+fn f64_from_bytes(buf: &[u8],big_endian: bool) -> f64 {
+    let mut bytes = [0u8;8];
+    bytes.copy_from_slice(&buf[..8]);
+    if big_endian {
+        f64::from_be_bytes(bytes)
+    } else {
+        f64::from_le_bytes(bytes)
+    }
+}
+
+/// Guesses, if the binary data of an ngspice file is stored big endian.
+///
+/// ngspice writes the doubles in the byte order of the machine and does not tell it in the
+/// description. The first values are read in both byte orders. The byte order with more
+/// plausible values (zero or between 1e-30 and 1e30 in magnitude) wins. Only the data of
+/// this plot is read, so the description of a following plot does not change the guess.
+pub fn detect_big_endian(buf: &[u8],header: &RawHeader) -> bool {
+    if header.simulator != Simulator::Ngspice {
+        return false;
+    }
+    let count = (buf.len().min(point_size(header) * header.nr_of_points) / 8).min(64);
+    let plausible = |value: f64| value == 0.0 || (value.abs() > 1e-30 && value.abs() < 1e30);
+    let little = (0..count).filter(|ct| plausible(f64_from_bytes(&buf[ct*8..],false))).count();
+    let big = (0..count).filter(|ct| plausible(f64_from_bytes(&buf[ct*8..],true))).count();
+    big > little
+}
+
+/// Returns the position of the first value of a variable in a fastaccess file, relative to
+/// the start of the binary data.
+pub fn column_offset(header: &RawHeader,var_index: usize) -> usize {
+    (0..var_index).map(|index| sample_size(header,index)).sum::<usize>() * header.nr_of_points
+}
+
+/// Reads the binary data of the file.
+///
+/// Normally the values are stored point by point. If the flag `fastaccess` is set, the values
+/// are stored variable by variable instead. With the feature `parallel` the points (or the
+/// variables of a fastaccess file) are decoded by several threads.
+pub fn decode_binary(file_buf: &[u8],header: &RawHeader,reals: &mut Vec<Vec<f64>>,imags: &mut Vec<Vec<f64>>) -> Result<(),ParseError> {
+    let expected = point_size(header) * header.nr_of_points;
+    if file_buf.len() < expected {
+        return Err(ParseError::TruncatedBinary {
+            expected: expected,
+            found: file_buf.len()
+        });
+    }
+
+    if header.flags.fastaccess {
+        decode_columns(file_buf,header,reals,imags)
+    } else {
+        decode_rows(&file_buf[..expected],header,reals,imags);
+        Ok(())
+    }
+}
+
+/// Decodes all points of the buffer, which are stored point by point, and appends them
+/// to `reals` and `imags`
+pub fn decode_points(file_buf: &[u8],header: &RawHeader,reals: &mut Vec<Vec<f64>>,imags: &mut Vec<Vec<f64>>) {
+    let mut freq_step_counter = 0;
+    let mut ct = 0;
+    while ct<file_buf.len() {
+        let (real,imag) = read_sample(&file_buf[ct..],header,freq_step_counter);
+        ct += sample_size(header,freq_step_counter);
+
+        reals[freq_step_counter].push(real);
+        imags[freq_step_counter].push(imag);
+
+        freq_step_counter = (freq_step_counter+1)%reals.len();
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+fn decode_rows(file_buf: &[u8],header: &RawHeader,reals: &mut Vec<Vec<f64>>,imags: &mut Vec<Vec<f64>>) {
+    decode_points(file_buf,header,reals,imags);
+}
+
+/// The number of points, which are decoded together by one thread
+#[cfg(feature = "parallel")]
+const POINTS_PER_CHUNK: usize = 16384;
+
+/// Splits the points into chunks, which are decoded in parallel and appended in their order
+#[cfg(feature = "parallel")]
+fn decode_rows(file_buf: &[u8],header: &RawHeader,reals: &mut Vec<Vec<f64>>,imags: &mut Vec<Vec<f64>>) {
+    let chunks: Vec<(Vec<Vec<f64>>,Vec<Vec<f64>>)> = file_buf.par_chunks(point_size(header) * POINTS_PER_CHUNK).map(|chunk| {
+        let mut chunk_reals = vec![Vec::new();header.nr_of_variables];
+        let mut chunk_imags = vec![Vec::new();header.nr_of_variables];
+        decode_points(chunk,header,&mut chunk_reals,&mut chunk_imags);
+        (chunk_reals,chunk_imags)
+    }).collect();
+    for (chunk_reals,chunk_imags) in chunks {
+        for (column,values) in reals.iter_mut().zip(chunk_reals) {
+            column.extend(values);
+        }
+        for (column,values) in imags.iter_mut().zip(chunk_imags) {
+            column.extend(values);
+        }
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+fn decode_columns(file_buf: &[u8],header: &RawHeader,reals: &mut Vec<Vec<f64>>,imags: &mut Vec<Vec<f64>>) -> Result<(),ParseError> {
+    for var_index in 0..header.nr_of_variables {
+        let (column_reals,column_imags) = decode_column(&file_buf[column_offset(header,var_index)..],header,var_index)?;
+        reals[var_index] = column_reals;
+        imags[var_index] = column_imags;
+    }
+    Ok(())
+}
+
+/// Decodes the variables of a fastaccess file in parallel
+#[cfg(feature = "parallel")]
+fn decode_columns(file_buf: &[u8],header: &RawHeader,reals: &mut Vec<Vec<f64>>,imags: &mut Vec<Vec<f64>>) -> Result<(),ParseError> {
+    let columns: Vec<(Vec<f64>,Vec<f64>)> = (0..header.nr_of_variables).into_par_iter()
+        .map(|var_index| decode_column(&file_buf[column_offset(header,var_index)..],header,var_index))
+        .collect::<Result<_,_>>()?;
+    for (var_index,(column_reals,column_imags)) in columns.into_iter().enumerate() {
+        reals[var_index] = column_reals;
+        imags[var_index] = column_imags;
+    }
+    Ok(())
+}
+
+/// Reads all values of one variable of a fastaccess file.
+///
+/// The buffer has to start at the first value of the variable (see `column_offset`).
+pub fn decode_column(column_buf: &[u8],header: &RawHeader,var_index: usize) -> Result<(Vec<f64>,Vec<f64>),ParseError> {
+    let size = sample_size(header,var_index);
+    let nr_of_points = header.nr_of_points;
+    if column_buf.len() < size * nr_of_points {
+        return Err(ParseError::TruncatedBinary {
+            expected: size * nr_of_points,
+            found: column_buf.len()
+        });
+    }
+    let mut reals = Vec::with_capacity(nr_of_points);
+    let mut imags = Vec::with_capacity(nr_of_points);
+    for point in 0..nr_of_points {
+        let (real,imag) = read_sample(&column_buf[point*size..],header,var_index);
+        reals.push(real);
+        imags.push(imag);
+    }
+    Ok((reals,imags))
+}
+
+
+/// Replaces the negative values of the time of a `.tran` plot by their absolute values and
+/// returns the indices of these points (see `SteppedSimulation::compressed_points`).
+pub fn normalise_time(time: &mut [f64]) -> Vec<usize> {
+    let mut marked = Vec::new();
+    for (point,value) in time.iter_mut().enumerate() {
+        if *value < 0.0 {
+            *value = -*value;
+            marked.push(point);
+        }
+    }
+    marked
+}
+
+/// Reads the ASCII data of the file, which follows "Values:".
+///
+/// Every point starts with its index, followed by the values of all variables.
+/// Complex values are written as `real,imaginary`.
+pub fn decode_ascii(file_buf: &[u8],encoding: Encoding,header: &RawHeader,reals: &mut Vec<Vec<f64>>,imags: &mut Vec<Vec<f64>>) -> Result<(),ParseError> {
+    let text = encoding.decode(file_buf);
+    let mut tokens = text.split_whitespace();
+    let nr_of_points = header.nr_of_points;
+
+    for point in 0..nr_of_points {
+        match tokens.next() {
+            Some(index) => if usize::from_str(index).is_err() {
+                return Err(ParseError::MalformedValue(index.to_owned()));
+            },
+            None => return Err(ParseError::TruncatedValues {
+                expected: nr_of_points,
+                found: point
+            })
+        }
+        for var_counter in 0..header.nr_of_variables {
+            let token = match tokens.next() {
+                Some(token) => token,
+                None => return Err(ParseError::TruncatedValues {
+                    expected: nr_of_points,
+                    found: point
+                })
+            };
+            let (real,imag) = match parse_ascii_value(token) {
+                Some(value) => value,
+                None => return Err(ParseError::MalformedValue(token.to_owned()))
+            };
+            reals[var_counter].push(real);
+            imags[var_counter].push(imag);
+        }
+    }
+    Ok(())
+}
+
+/// Parses a value like `1.5e-003` or `1.5e-003,-2.0e+000` (complex)
+fn parse_ascii_value(token: &str) -> Option<(f64,f64)> {
+    let mut parts = token.splitn(2,',');
+    let real = match parts.next().map(f64::from_str) {
+        Some(Ok(real)) => real,
+        _ => return None
+    };
+    match parts.next().map(f64::from_str) {
+        Some(Ok(imag)) => Some((real,imag)),
+        Some(Err(_)) => None,
+        None => Some((real,0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::detect_big_endian;
+    use header::RawHeader;
+
+    fn header(command: &str, plot_name: &str, flags: &str, nr_of_points: usize) -> RawHeader {
+        let text = format!("Title: * test\nPlotname: {}\nFlags: {}\nNo. Variables: 2\nNo. Points: {}\nCommand: {}\n\
+                            Variables:\n\t0\tv(out)\tvoltage\n\t1\ti(v1)\tcurrent\nBinary:\n", plot_name, flags, nr_of_points, command);
+        RawHeader::parse(&text).unwrap().0
+    }
+
+    fn doubles(values: &[f64], big_endian: bool) -> Vec<u8> {
+        values.iter().flat_map(|value| if big_endian { value.to_be_bytes() } else { value.to_le_bytes() }).collect()
+    }
+
+    #[test]
+    fn byte_order_of_ngspice_plots() {
+        let op = header("version 36", "Operating Point", "real", 1);
+        let ac = header("version 36", "AC Analysis", "complex", 3);
+        for &big_endian in &[false, true] {
+            // The operating point is followed by the description and the data of an AC analysis
+            let mut buf = doubles(&[1.5, -2e-3], big_endian);
+            buf.extend_from_slice(b"Title: * test\nPlotname: AC Analysis\nFlags: complex\nBinary:\n");
+            buf.extend(doubles(&[10.0, 0.0, 1.0, -0.1, 100.0, 0.0, 0.5, -0.5, 1e3, 0.0, 0.1, -0.3], big_endian));
+            assert_eq!(detect_big_endian(&buf, &op), big_endian);
+            let ac_data = buf.len() - 12 * 8;
+            assert_eq!(detect_big_endian(&buf[ac_data..], &ac), big_endian);
+        }
+    }
+
+    #[test]
+    fn ltspice_is_little_endian() {
+        let op = header("Linear Technology Corporation LTspice XVII", "Operating Point", "real double", 1);
+        assert!(!detect_big_endian(&doubles(&[1.5, -2e-3], true), &op));
+    }
+}
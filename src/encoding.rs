@@ -0,0 +1,95 @@
+//! Detection and decoding of the text encoding used by LTspice
+//!
+//! LTspice IV writes the ASCII description of the `.raw`-File (and the `.log`-File) in plain
+//! ASCII. LTspice XVII and LTspice 24 write the same text as UTF-16 little endian, sometimes
+//! with a byte order mark in front of it.
+
+/// The text encoding of a file written by LTspice
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Encoding {
+    /// ASCII or UTF-8 (LTspice IV)
+    Utf8,
+    /// UTF-16 little endian (LTspice XVII and LTspice 24)
+    Utf16Le
+}
+
+impl Encoding {
+    /// Guesses the encoding from the first bytes of a file.
+    ///
+    /// Returns the encoding and the length of the byte order mark, which has to be skipped.
+    pub fn detect(buf: &[u8]) -> (Encoding, usize) {
+        if buf.starts_with(&[0xFF, 0xFE]) {
+            (Encoding::Utf16Le, 2)
+        } else if buf.starts_with(&[0xEF, 0xBB, 0xBF]) {
+            (Encoding::Utf8, 3)
+        } else if buf.len() >= 2 && buf[0] != 0 && buf[1] == 0 {
+            // An ASCII character followed by a zero byte can only be UTF-16LE
+            (Encoding::Utf16Le, 0)
+        } else {
+            (Encoding::Utf8, 0)
+        }
+    }
+
+    /// Returns the width of one (ASCII) character in bytes
+    pub fn char_width(&self) -> usize {
+        match *self {
+            Encoding::Utf8 => 1,
+            Encoding::Utf16Le => 2
+        }
+    }
+
+    /// Converts the given text to the bytes, which are found in a file with this encoding
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        match *self {
+            Encoding::Utf8 => text.as_bytes().to_vec(),
+            Encoding::Utf16Le => {
+                let mut result = Vec::with_capacity(text.len() * 2);
+                for unit in text.encode_utf16() {
+                    result.push((unit & 0xFF) as u8);
+                    result.push((unit >> 8) as u8);
+                }
+                result
+            }
+        }
+    }
+
+    /// Converts the bytes of a file with this encoding to a String.
+    /// Invalid characters are replaced instead of failing.
+    pub fn decode(&self, buf: &[u8]) -> String {
+        match *self {
+            Encoding::Utf8 => String::from_utf8_lossy(buf).into_owned(),
+            Encoding::Utf16Le => {
+                let units: Vec<u16> = buf.chunks(2)
+                    .filter(|chunk| chunk.len() == 2)
+                    .map(|chunk| chunk[0] as u16 | (chunk[1] as u16) << 8)
+                    .collect();
+                String::from_utf16_lossy(&units)
+            }
+        }
+    }
+
+    /// Searches for the encoded text in the buffer and returns the position of its first byte.
+    ///
+    /// For UTF-16 only positions at character boundaries are considered.
+    pub fn find(&self, buf: &[u8], text: &str) -> Option<usize> {
+        let needle = self.encode(text);
+        if needle.len() > buf.len() {
+            return None;
+        }
+        let width = self.char_width();
+        let mut pos = 0;
+        while pos + needle.len() <= buf.len() {
+            if &buf[pos..pos + needle.len()] == &needle[..] {
+                return Some(pos);
+            }
+            pos += width;
+        }
+        None
+    }
+}
+
+/// Decodes a whole text file (e.g. the `.log`-File), whatever encoding LTspice used for it
+pub fn decode_text(buf: &[u8]) -> String {
+    let (encoding, bom) = Encoding::detect(buf);
+    encoding.decode(&buf[bom..])
+}
@@ -0,0 +1,89 @@
+//! This module contains the error type of the parser
+//!
+//! All functions, which read files, return a `ParseError` instead of panicking, so a single
+//! broken file does not stop the evaluation of all other files.
+use std::error;
+use std::fmt;
+use std::io;
+use results::Step;
+
+/// Describes, why a simulation could not be read or evaluated
+#[derive(Debug)]
+pub enum ParseError {
+    /// The file could not be opened or read
+    Io(io::Error),
+    /// A line of the ASCII description, the ASCII data or the `.log`-File could not be understood
+    MalformedHeader(String),
+    /// The `Flags:` line contains a flag, which is not known to this parser
+    UnknownFlag(String),
+    /// The binary data ends before all points were read. Both sizes are given in bytes.
+    TruncatedBinary {
+        expected: usize,
+        found: usize
+    },
+    /// The ASCII data ends before all points were read. Both sizes are given in points.
+    TruncatedValues {
+        expected: usize,
+        found: usize
+    },
+    /// A value (or the index of a point) in the ASCII data is not a number
+    MalformedValue(String),
+    /// The number of blocks of the sweep variable in the data does not fit to the number of
+    /// steps in the `.log`-File
+    StepCountMismatch {
+        blocks: usize,
+        steps: usize
+    },
+    /// The data does not contain the number of points given by `No. Points`
+    PointCountMismatch {
+        expected: usize,
+        found: usize
+    },
+    /// A variable with the given name is not part of the simulation
+    MissingVariable(String),
+    /// The step is not part of the simulation
+    MissingStep(Step),
+    /// The searched frequency is higher than all frequencies of the simulation
+    FrequencyNotFound(f64),
+    /// The file is valid, but the requested way of reading it is not possible (e.g. memory
+    /// mapping a file with ASCII data)
+    UnsupportedFormat(String)
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::Io(ref err) => write!(f, "could not read file: {}", err),
+            ParseError::MalformedHeader(ref line) => write!(f, "malformed line: {:?}", line),
+            ParseError::UnknownFlag(ref flag) => write!(f, "unknown flag: {:?}", flag),
+            ParseError::TruncatedBinary { expected, found } =>
+                write!(f, "binary data is truncated: expected {} bytes, found {}", expected, found),
+            ParseError::TruncatedValues { expected, found } =>
+                write!(f, "ASCII data is truncated: expected {} points, found {}", expected, found),
+            ParseError::MalformedValue(ref value) => write!(f, "malformed value: {:?}", value),
+            ParseError::StepCountMismatch { blocks, steps } =>
+                write!(f, "the data contains {} sweeps, which do not fit to {} steps", blocks, steps),
+            ParseError::PointCountMismatch { expected, found } =>
+                write!(f, "the data contains {} points instead of {}", found, expected),
+            ParseError::MissingVariable(ref name) => write!(f, "variable {:?} is not part of the simulation", name),
+            ParseError::MissingStep(ref step) => write!(f, "step {:?} is not part of the simulation", step),
+            ParseError::FrequencyNotFound(freq) => write!(f, "frequency {} is not part of the simulation", freq),
+            ParseError::UnsupportedFormat(ref reason) => write!(f, "unsupported format: {}", reason)
+        }
+    }
+}
+
+impl error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            ParseError::Io(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(err: io::Error) -> Self {
+        ParseError::Io(err)
+    }
+}
@@ -0,0 +1,353 @@
+//! This module contains the ASCII description at the start of a `.raw`-File
+//!
+//! The description tells, which analysis was run, how the data is stored and which variables
+//! are part of the simulation. It is available as a `RawHeader` from `SteppedSimulation::header`.
+use std::fmt;
+use std::str::FromStr;
+
+use error::ParseError;
+use results::{SimulationVariable, VariableType};
+
+
+/// The ASCII description of a `.raw`-File
+///
+/// # Examples
+/// ```no_run
+/// # use ltspice_parse::SteppedSimulation;
+/// # use ltspice_parse::header::PlotKind;
+/// # let results = SteppedSimulation::from_raw_file("Draft2.raw").unwrap();
+/// let header = results.header();
+/// assert_eq!(header.plot_kind, PlotKind::Ac);
+/// assert!(header.flags.stepped);
+/// ```
+#[derive(Debug,Clone,PartialEq)]
+pub struct RawHeader {
+    /// The title of the simulation. For LTspice this is the name of the schematic, e.g. `* Draft2.asc`
+    pub title: String,
+    /// The date of the simulation. This is None, if the date could not be parsed.
+    pub date: Option<RawDate>,
+    /// The name of the plot as it is written in the file, e.g. `AC Analysis`
+    pub plot_name: String,
+    /// The analysis, which produced the plot
+    pub plot_kind: PlotKind,
+    /// The flags, which describe how the values are stored
+    pub flags: Flags,
+    /// The number of variables of every point
+    pub nr_of_variables: usize,
+    /// The number of points of all steps together
+    pub nr_of_points: usize,
+    /// The offset of the sweep variable. This is usually zero.
+    pub offset: f64,
+    /// The `Command:` line, which identifies the program that wrote the file
+    pub command: String,
+    /// The program, which wrote the file
+    pub simulator: Simulator,
+    /// The version of LTspice (e.g. `IV`, `XVII` or `24.0.12`), if the file was written by LTspice
+    pub ltspice_version: Option<String>,
+    /// The binary values are stored big endian. Only ngspice writes such files (on big endian
+    /// machines), so this is guessed from the values of ngspice files.
+    pub big_endian: bool,
+    /// All `Backannotation:` lines, which LTspice uses to map the netlist to the schematic
+    pub backannotations: Vec<String>
+}
+
+/// The date of a simulation, as written in the `Date:` line (e.g. `Thu Jan 14 17:03:57 2016`)
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct RawDate {
+    pub year: u16,
+    /// The month, starting with 1 for January
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8
+}
+
+/// The analysis, which is stored in the file. It is determined by the `Plotname:` line.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum PlotKind {
+    /// `.ac` - complex values over the frequency
+    Ac,
+    /// `.tran` - real values over the time
+    Transient,
+    /// `.op` - one real value for every variable
+    OperatingPoint,
+    /// `.dc` - real values over a swept source
+    DcSweep,
+    /// `.tf` - the small signal transfer function
+    TransferFunction,
+    /// `.noise` - noise spectral densities over the frequency
+    Noise,
+    /// Any other plot. Contains the plot name.
+    Unknown(String)
+}
+
+/// The program, which wrote the file. It is determined by the `Command:` line.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Simulator {
+    /// `Command: Linear Technology Corporation LTspice XVII`
+    LTspice,
+    /// `Command: version 36` or `Command: ngspice-36`. ngspice stores all real values as doubles.
+    Ngspice,
+    Unknown
+}
+
+/// The flags of the `Flags:` line
+#[derive(Debug,Clone,Copy,Default,PartialEq,Eq)]
+pub struct Flags {
+    /// Every value has a real and an imaginary part
+    pub complex: bool,
+    /// Every value only has a real part
+    pub real: bool,
+    /// The sweep variable counts forwards
+    pub forward: bool,
+    /// The sweep variable has a logarithmic scale
+    pub log: bool,
+    /// The sweep variable has a linear scale
+    pub linear: bool,
+    /// The sweep variable is swept in octaves
+    pub octave: bool,
+    /// Some parameters are stepped with the `.step` command
+    pub stepped: bool,
+    /// All real values are stored as doubles
+    pub double: bool,
+    /// The values are stored variable by variable
+    pub fastaccess: bool
+}
+
+impl PlotKind {
+    /// Returns false for plots without a sweep variable (`.op` and `.tf`). In these plots
+    /// every point contains one scalar value per variable and the first variable is not
+    /// an axis like the time or the frequency.
+    pub fn has_sweep_variable(&self) -> bool {
+        match *self {
+            PlotKind::OperatingPoint | PlotKind::TransferFunction => false,
+            _ => true
+        }
+    }
+
+    /// Determines the kind of the plot from the text of the `Plotname:` line
+    pub fn from_plot_name(plot_name: &str) -> PlotKind {
+        let lower = plot_name.trim().to_lowercase();
+        if lower.starts_with("ac analysis") {
+            PlotKind::Ac
+        } else if lower.starts_with("transient analysis") {
+            PlotKind::Transient
+        } else if lower.starts_with("operating point") {
+            PlotKind::OperatingPoint
+        } else if lower.starts_with("dc transfer characteristic") {
+            PlotKind::DcSweep
+        } else if lower.starts_with("transfer function") {
+            PlotKind::TransferFunction
+        } else if lower.contains("noise") {
+            PlotKind::Noise
+        } else {
+            PlotKind::Unknown(plot_name.trim().to_owned())
+        }
+    }
+}
+
+impl Simulator {
+    /// Determines the simulator from the text of the `Command:` line
+    pub fn from_command(command: &str) -> Simulator {
+        let lower = command.trim().to_lowercase();
+        if lower.contains("ltspice") {
+            Simulator::LTspice
+        } else if lower.contains("ngspice") || lower.starts_with("version") {
+            Simulator::Ngspice
+        } else {
+            Simulator::Unknown
+        }
+    }
+}
+
+impl Flags {
+    /// Parses the flags after `Flags:`. Returns an error for flags, which are not known.
+    pub fn parse(text: &str) -> Result<Flags,ParseError> {
+        let mut flags = Flags::default();
+        for flag in text.split_whitespace() {
+            match flag.to_lowercase().as_str() {
+                "complex" => flags.complex = true,
+                "real" => flags.real = true,
+                "forward" => flags.forward = true,
+                "log" => flags.log = true,
+                "linear" => flags.linear = true,
+                "octave" => flags.octave = true,
+                "stepped" => flags.stepped = true,
+                "double" => flags.double = true,
+                "fastaccess" => flags.fastaccess = true,
+                // ngspice marks, if every point contains all variables. This is always the case.
+                "padded" | "unpadded" => {},
+                other => return Err(ParseError::UnknownFlag(other.to_owned()))
+            }
+        }
+        Ok(flags)
+    }
+}
+
+impl fmt::Display for Flags {
+    /// Writes the flags in the order used by LTspice, e.g. `complex forward log stepped`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let flags = [
+            (self.complex,"complex"),
+            (self.real,"real"),
+            (self.forward,"forward"),
+            (self.log,"log"),
+            (self.linear,"linear"),
+            (self.octave,"octave"),
+            (self.stepped,"stepped"),
+            (self.double,"double"),
+            (self.fastaccess,"fastaccess")
+        ];
+        let names: Vec<&str> = flags.iter().filter(|flag| flag.0).map(|flag| flag.1).collect();
+        write!(f, "{}", names.join(" "))
+    }
+}
+
+const MONTHS: [&'static str;12] = ["Jan","Feb","Mar","Apr","May","Jun","Jul","Aug","Sep","Oct","Nov","Dec"];
+
+impl RawDate {
+    /// Parses a date like `Thu Jan 14 17:03:57 2016`
+    pub fn parse(text: &str) -> Option<RawDate> {
+        let parts: Vec<&str> = text.split_whitespace().collect();
+        if parts.len() != 5 {
+            return None;
+        }
+        let month = MONTHS.iter().position(|month| parts[1].to_lowercase().starts_with(&month.to_lowercase()));
+        let time: Vec<Option<u8>> = parts[3].split(':').map(|part| u8::from_str(part).ok()).collect();
+        match (month,u8::from_str(parts[2]),u16::from_str(parts[4]),time.as_slice()) {
+            (Some(month),Ok(day),Ok(year),&[Some(hour),Some(minute),Some(second)]) => Some(RawDate {
+                year: year,
+                month: month as u8 + 1,
+                day: day,
+                hour: hour,
+                minute: minute,
+                second: second
+            }),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for RawDate {
+    /// Writes the date like LTspice, e.g. `Thu Jan 14 17:03:57 2016`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const WEEKDAYS: [&'static str;7] = ["Sun","Mon","Tue","Wed","Thu","Fri","Sat"];
+        const MONTH_OFFSETS: [u32;12] = [0,3,2,5,0,3,5,1,4,6,2,4];
+        // The weekday of the Gregorian calendar (Sakamoto's method)
+        let month = (self.month.max(1).min(12) - 1) as usize;
+        let year = if month < 2 { (self.year as u32).saturating_sub(1) } else { self.year as u32 };
+        let weekday = (year + year/4 - year/100 + year/400 + MONTH_OFFSETS[month] + self.day as u32) % 7;
+        write!(f, "{} {} {:2} {:02}:{:02}:{:02} {}", WEEKDAYS[weekday as usize], MONTHS[month],
+            self.day, self.hour, self.minute, self.second, self.year)
+    }
+}
+
+impl RawHeader {
+    /// Parses the ASCII description of a `.raw`-File, which has already been decoded to a String.
+    ///
+    /// Returns the header together with all variables of the `Variables:` section.
+    pub fn parse(text: &str) -> Result<(RawHeader,Vec<SimulationVariable>),ParseError> {
+        let mut header = RawHeader {
+            title: String::new(),
+            date: None,
+            plot_name: String::new(),
+            plot_kind: PlotKind::Unknown(String::new()),
+            flags: Flags::default(),
+            nr_of_variables: 0,
+            nr_of_points: 0,
+            offset: 0.0,
+            command: String::new(),
+            simulator: Simulator::Unknown,
+            ltspice_version: None,
+            big_endian: false,
+            backannotations: Vec::new()
+        };
+        let mut variables = Vec::new();
+        let mut in_variables = false;
+
+        for line in text.lines() {
+            let line = line.trim_end_matches('\r');
+            if in_variables && (line.starts_with('\t') || line.starts_with(' ')) {
+                variables.push(parse_variable(line)?);
+                continue;
+            }
+            in_variables = false;
+            let (key,value) = match line.find(':') {
+                Some(pos) => (&line[..pos],line[pos+1..].trim()),
+                None => continue
+            };
+            match key {
+                "Title" => header.title = value.to_owned(),
+                "Date" => header.date = RawDate::parse(value),
+                "Plotname" => {
+                    header.plot_name = value.to_owned();
+                    header.plot_kind = PlotKind::from_plot_name(value);
+                },
+                "Flags" => header.flags = Flags::parse(value)?,
+                "No. Variables" => header.nr_of_variables = parse_header_value(line,value)?,
+                "No. Points" => header.nr_of_points = parse_header_value(line,value)?,
+                "Offset" => header.offset = parse_header_value(line,value)?,
+                "Command" => {
+                    header.command = value.to_owned();
+                    header.simulator = Simulator::from_command(value);
+                    header.ltspice_version = ltspice_version(value);
+                },
+                "Backannotation" => header.backannotations.push(value.to_owned()),
+                "Variables" => in_variables = true,
+                "Binary" | "Values" => break,
+                _ => {}
+            }
+        }
+
+        if header.nr_of_variables == 0 {
+            return Err(ParseError::MalformedHeader(String::from("no variables found")));
+        }
+        if variables.len() != header.nr_of_variables {
+            return Err(ParseError::MalformedHeader(format!("expected {} variables, found {}",header.nr_of_variables,variables.len())));
+        }
+        Ok((header,variables))
+    }
+}
+
+/// Parses a line of the `Variables:` section like `\t0\tfrequency\tfrequency`
+fn parse_variable(line: &str) -> Result<SimulationVariable,ParseError> {
+    let cols: Vec<&str> = line.split('\t').map(|col| col.trim()).filter(|col| !col.is_empty()).collect();
+    if cols.len() < 3 {
+        return Err(ParseError::MalformedHeader(line.to_owned()));
+    }
+    let id = match u16::from_str(cols[0]) {
+        Ok(id) => id,
+        Err(_) => return Err(ParseError::MalformedHeader(line.to_owned()))
+    };
+    let vt = VariableType::from_type_name(cols[2]);
+    Ok(SimulationVariable {
+        id:id,
+        name:String::from(cols[1]),
+        var_type:vt,
+        type_name:String::from(cols[2])
+    })
+}
+
+/// Parses the value after the colon of a line like `No. Points: 42`
+fn parse_header_value<T: FromStr>(line: &str,value: &str) -> Result<T,ParseError> {
+    match T::from_str(value) {
+        Ok(value) => Ok(value),
+        Err(_) => Err(ParseError::MalformedHeader(line.to_owned()))
+    }
+}
+
+/// Extracts the version from a command like `Linear Technology Corporation LTspice XVII`
+/// or `LTspice 24.0.12 for Windows`
+fn ltspice_version(command: &str) -> Option<String> {
+    let pos = match command.find("LTspice") {
+        Some(pos) => pos + "LTspice".len(),
+        None => return None
+    };
+    let version: Vec<&str> = command[pos..].split_whitespace().take_while(|word| *word != "for").collect();
+    if version.is_empty() {
+        None
+    } else {
+        Some(version.join(" "))
+    }
+}
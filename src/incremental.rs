@@ -0,0 +1,212 @@
+//! An incremental reader for `.raw`-Files, which are still written by a running simulation
+//!
+//! LTspice writes the ASCII description first and appends every point as soon as it is
+//! calculated. An `IncrementalReader` can be polled to read the points, which were appended
+//! since the last poll. A point, which is only partially written, is kept until it is complete.
+//!
+//! # Examples
+//! ```no_run
+//! # use std::thread;
+//! # use std::time::Duration;
+//! # use ltspice_parse::incremental::IncrementalReader;
+//! let mut reader = IncrementalReader::new("Draft2.raw");
+//! while !reader.is_complete() {
+//!     reader.poll().unwrap();
+//!     for range in reader.completed_steps() {
+//!         // check the finished steps
+//!     }
+//!     thread::sleep(Duration::from_secs(1));
+//! }
+//! let results = reader.into_simulation().unwrap();
+//! ```
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use decode;
+use error::ParseError;
+use header::{PlotKind, RawHeader};
+use results::{SimulationVariable, StepRange, VariableResult};
+use {find_step_ranges, parse_description, read_header, read_log_file, Columns, DataFormat, SteppedSimulation};
+use measurements::Measurements;
+
+
+/// Reads the points of a growing binary `.raw`-File
+#[derive(Debug)]
+pub struct IncrementalReader {
+    path: PathBuf,
+    /// The description of the file. This is None, until "Binary:" was written.
+    header: Option<RawHeader>,
+    variables: Vec<SimulationVariable>,
+    /// The position of the first byte after "Binary:\n"
+    data_offset: usize,
+    /// The number of bytes of the data, which were already read
+    consumed: usize,
+    /// The bytes of a point, which was not written completely during the last poll
+    pending: Vec<u8>,
+    reals: Vec<Vec<f64>>,
+    imags: Vec<Vec<f64>>,
+    /// The indices of the compressed points (see `SteppedSimulation::compressed_points`)
+    compressed_points: Vec<usize>
+}
+
+impl IncrementalReader {
+    /// Creates a reader for the given `.raw`-File. The file is read by `poll`, so it does
+    /// not need to exist yet.
+    pub fn new<P: AsRef<Path>>(path_raw: P) -> Self {
+        IncrementalReader {
+            path: path_raw.as_ref().to_path_buf(),
+            header: None,
+            variables: Vec::new(),
+            data_offset: 0,
+            consumed: 0,
+            pending: Vec::new(),
+            reals: Vec::new(),
+            imags: Vec::new(),
+            compressed_points: Vec::new()
+        }
+    }
+
+    /// Reads all points, which were appended to the file since the last poll, and returns
+    /// their number.
+    ///
+    /// Returns 0, if the file or its ASCII description is not written completely yet. Only
+    /// binary files without fastaccess can be read incrementally.
+    pub fn poll(&mut self) -> Result<usize,ParseError> {
+        let mut file = match fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(_) if self.header.is_none() => return Ok(0),
+            Err(err) => return Err(ParseError::from(err))
+        };
+        let (file_buf,text,data_offset,format) = match read_header(&mut file) {
+            Ok(result) => result,
+            // "Binary:" is not written yet
+            Err(ParseError::MalformedHeader(_)) if self.header.is_none() => return Ok(0),
+            Err(err) => return Err(err)
+        };
+        if format != DataFormat::Binary {
+            return Err(ParseError::UnsupportedFormat(String::from("only binary data can be read incrementally")));
+        }
+        // The description is read again, because `No. Points` might be updated at the end
+        let (header,variables) = parse_description(&text,&file_buf[data_offset..],format)?;
+        if header.flags.fastaccess {
+            return Err(ParseError::UnsupportedFormat(String::from("fastaccess files can not be read incrementally")));
+        }
+        let big_endian = self.header.as_ref().map(|header| header.big_endian);
+        if big_endian != Some(header.big_endian) || data_offset != self.data_offset || variables != self.variables {
+            // The description changed, so all points are read again
+            self.reals = (0..variables.len()).map(|_| Vec::new()).collect();
+            self.imags = (0..variables.len()).map(|_| Vec::new()).collect();
+            self.variables = variables;
+            self.data_offset = data_offset;
+            self.consumed = 0;
+            self.pending.clear();
+            self.compressed_points.clear();
+        }
+        self.header = Some(header);
+
+        file.seek(SeekFrom::Start((self.data_offset + self.consumed + self.pending.len()) as u64))?;
+        file.read_to_end(&mut self.pending)?;
+        let header = self.header.as_ref().unwrap();
+        let point_size = decode::point_size(header);
+        let new_points = self.pending.len() / point_size;
+        let complete = new_points * point_size;
+        let first_new = self.nr_of_points();
+        decode::decode_points(&self.pending[..complete],header,&mut self.reals,&mut self.imags);
+        if header.plot_kind == PlotKind::Transient {
+            let marked = decode::normalise_time(&mut self.reals[0][first_new..]);
+            self.compressed_points.extend(marked.iter().map(|point| point + first_new));
+        }
+        self.pending.drain(..complete);
+        self.consumed += complete;
+        Ok(new_points)
+    }
+
+    /// Returns the ASCII description of the file, if it was already read
+    pub fn header(&self) -> Option<&RawHeader> {
+        self.header.as_ref()
+    }
+
+    /// Returns a vector of all variables of the file. It is empty before the description was read.
+    pub fn available_variables(&self) -> &Vec<SimulationVariable> {
+        &self.variables
+    }
+
+    /// Returns the number of points, which were read so far
+    pub fn nr_of_points(&self) -> usize {
+        self.reals.first().map(|reals| reals.len()).unwrap_or(0)
+    }
+
+    /// Returns true, if all points given by `No. Points` were read
+    pub fn is_complete(&self) -> bool {
+        match self.header {
+            Some(ref header) => header.nr_of_points > 0 && self.nr_of_points() >= header.nr_of_points,
+            None => false
+        }
+    }
+
+    /// Returns the indices of the compressed points (see `SteppedSimulation::compressed_points`).
+    /// The indices count from the start of the file.
+    pub fn compressed_points(&self) -> &Vec<usize> {
+        &self.compressed_points
+    }
+
+    /// Returns the position of every step, which was read so far. The last step might still
+    /// be running (see `completed_steps`).
+    pub fn step_ranges(&self) -> Vec<StepRange> {
+        match self.header {
+            Some(ref header) if self.nr_of_points() > 0 => if header.plot_kind.has_sweep_variable() {
+                find_step_ranges(&self.reals[0])
+            } else {
+                (0..self.nr_of_points()).map(|ct| StepRange { offset: ct, len: 1 }).collect()
+            },
+            _ => Vec::new()
+        }
+    }
+
+    /// Returns the position of every step, which is finished. A step is finished, when the
+    /// next step has started or when the file is complete.
+    pub fn completed_steps(&self) -> Vec<StepRange> {
+        let mut step_ranges = self.step_ranges();
+        let has_sweep = self.header.as_ref().map(|header| header.plot_kind.has_sweep_variable()).unwrap_or(false);
+        if has_sweep && !self.is_complete() {
+            step_ranges.pop();
+        }
+        step_ranges
+    }
+
+    /// Returns the values of one variable in a range, which was returned by `step_ranges`
+    /// or `completed_steps`. Returns None, if the range has not been read yet.
+    pub fn get_values_for_variable_in(&self, range: StepRange, var: &SimulationVariable) -> Option<VariableResult> {
+        let var_pos = match self.variables.iter().position(|r| r.eq(var)) {
+            Some(var_pos) => var_pos,
+            None => return None
+        };
+        let end = range.offset + range.len;
+        if end > self.nr_of_points() {
+            return None;
+        }
+        Some(VariableResult::new(&self.variables[var_pos],self.reals[var_pos][range.offset..end].to_vec(),self.imags[var_pos][range.offset..end].to_vec()))
+    }
+
+    /// Converts the reader into a `SteppedSimulation`, after the file is complete.
+    ///
+    /// Like `SteppedSimulation::from_raw_file` the parameters of the steps are read from a
+    /// `.log`-File with the same name, if there is one.
+    pub fn into_simulation(self) -> Result<SteppedSimulation,ParseError> {
+        let header = match self.header {
+            Some(header) => header,
+            None => return Err(ParseError::MalformedHeader(String::from("no \"Binary:\" line found")))
+        };
+        let mut steps = Vec::new();
+        let mut measurements = Measurements::default();
+        let path_log = self.path.with_extension("log");
+        if path_log.is_file() {
+            read_log_file(&path_log,&mut steps,&mut measurements)?;
+        }
+        let mut simulation = SteppedSimulation::from_parts(steps,measurements,self.variables,Columns::Loaded { reals: self.reals, imags: self.imags },header,None)?;
+        // The time was already normalised during polling
+        simulation.compressed_points = self.compressed_points;
+        Ok(simulation)
+    }
+}
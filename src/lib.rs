@@ -2,28 +2,41 @@
 //!
 //! This library reads the *.raw files generated by LTSpice to apply functions on it
 //! 
-extern crate byteorder;
 extern crate num;
 extern crate gnuplot;
 extern crate statistical;
+extern crate memmap;
+#[cfg(feature = "parallel")]
+extern crate rayon;
 
 
 	
 	
 use std::fs;
-use std::io::{Read, BufReader, BufRead};
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::sync::Mutex;
 
 
 
-use byteorder::{ByteOrder, LittleEndian};
-
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use statistical::*;
 use results::*;
-use std::str::FromStr;
+use encoding::Encoding;
+pub use error::ParseError;
+use header::{RawHeader, PlotKind};
+use measurements::Measurements;
 
 pub mod results;
+pub mod error;
+pub mod header;
+pub mod units;
+pub mod mapped;
+pub mod incremental;
+pub mod writer;
+pub mod measurements;
+mod encoding;
+mod decode;
 
 //
 
@@ -32,6 +45,8 @@ pub mod results;
 /// 
 /// # Examples
 /// ```no_run
+/// # use std::path::PathBuf;
+/// # use ltspice_parse::SteppedSimulation;
 /// let mut path = PathBuf::from("./simulations/");
 ///	let model_name = "simulation1";
 ///
@@ -39,45 +54,327 @@ pub mod results;
 ///	
 ///	let results = SteppedSimulation::from_files(
 /// 	path.with_extension("raw").as_path(),
-///		path.with_extension("log").as_path());
+///		path.with_extension("log").as_path()).unwrap();
 ///	``` 
-/// This can currently only read results, which are generated with the 
-/// `.ac` command. If you want to read transient files, this library has
-/// to be extended 
+/// This can read results, which are generated with the `.ac` or the `.tran`
+/// command. Transient results are real valued, so their imaginary parts
+/// are always zero.
+///
+/// Results of `.op` and `.tf` contain a single point per step. Their values
+/// can be read with `get_scalar_for_name` and `get_scalars_at`. The nested
+/// blocks of a `.dc` sweep with two sources can be read with `get_sub_blocks_at`.
 
 #[derive(Debug)]
 pub struct SteppedSimulation {
     steps:Vec<Step>,
     variables: Vec<SimulationVariable>,
-    reals: Vec<Vec<f64>>,
-    imags: Vec<Vec<f64>>,
-    simulation_points: i32,
-    points_per_block: usize
+    columns: Columns,
+    header: RawHeader,
+    /// The position of every step in the values of the variables
+    step_ranges: Vec<StepRange>,
+    /// The position of every run of the sweep variable. This only differs from `step_ranges`
+    /// for `.dc` sweeps with two sources, where every step contains several blocks.
+    sweep_blocks: Vec<StepRange>,
+    /// Is set, if the file was read with `recover_raw_file` and did not contain all points
+    truncation: Option<Truncation>,
+    /// The results of the `.meas` statements in the `.log`-File
+    measurements: Measurements,
+    /// The indices of the compressed points in the whole file (see `compressed_points`)
+    compressed_points: Vec<usize>
 }
 
 
 impl SteppedSimulation {
 	/// Reads the Simulation result form the `.raw`-File. This also needs the 
 	/// `.log`-File to get informations about the steps.
-    pub fn from_files<P: AsRef<Path>>(path_raw: P, path_log: P) -> Self {
+	///
+	/// If the simulation has no `.step` command, the whole file is one step without parameters.
+	/// The steps are found in the data itself (see `from_raw_file`), so they may have different
+	/// lengths. Steps with a single point (e.g. `.ac list 1k`) can not be found in the data, so
+	/// the points are divided equally among the steps of the `.log`-File instead. If the number
+	/// of steps in the data differs from the `.log`-File, an error is returned.
+    pub fn from_files<P: AsRef<Path>>(path_raw: P, path_log: P) -> Result<Self,ParseError> {
         let mut steps = Vec::new();
+        let mut measurements = Measurements::default();
         let mut variables = Vec::new();
         let mut reals = Vec::new();
         let mut imags = Vec::new();
 
-        read_log_file(path_log,&mut steps);
-        let simulation_points = read_raw_file(path_raw,&mut variables,&mut reals,&mut imags);
-        let ppb = (simulation_points/steps.len()as i32) as usize;
-        SteppedSimulation {
-            steps: steps,
-            variables: variables,
-            reals: reals,
-            imags: imags,
-            simulation_points: simulation_points,
-            points_per_block: ppb
-        }
+        read_log_file(path_log,&mut steps,&mut measurements)?;
+        let header = read_raw_file(path_raw,&mut variables,&mut reals,&mut imags)?;
+        SteppedSimulation::from_parts(steps,measurements,variables,Columns::Loaded { reals: reals, imags: imags },header,None)
     }
 
+	/// Reads the Simulation result from the `.raw`-File only.
+	///
+	/// The steps are found in the data itself: A new step starts, whenever the sweep variable
+	/// (the first variable, e.g. the time or the frequency) returns to its start value. So the
+	/// steps may have different lengths.
+	///
+	/// If there is a `.log`-File with the same name next to the `.raw`-File, the parameters
+	/// of the steps are read from it. Otherwise every step gets a single parameter called
+	/// `step`, which counts the steps starting with 1.
+	pub fn from_raw_file<P: AsRef<Path>>(path_raw: P) -> Result<Self,ParseError> {
+		let mut steps = Vec::new();
+		let mut measurements = Measurements::default();
+		let mut variables = Vec::new();
+		let mut reals = Vec::new();
+		let mut imags = Vec::new();
+
+		let header = read_raw_file(&path_raw,&mut variables,&mut reals,&mut imags)?;
+		let path_log = path_raw.as_ref().with_extension("log");
+		if path_log.is_file() {
+			read_log_file(&path_log,&mut steps,&mut measurements)?;
+		}
+		SteppedSimulation::from_parts(steps,measurements,variables,Columns::Loaded { reals: reals, imags: imags },header,None)
+	}
+
+	/// Reads a `.raw`-File, which might end in the middle of a point, because the simulation
+	/// was aborted or killed.
+	///
+	/// All complete points are read like in `from_raw_file`, the rest of the file is ignored.
+	/// `truncation` tells, how many points were expected and how many were found. The last step
+	/// of a truncated file is marked as incomplete (see `is_step_complete`). `header` still
+	/// returns the number of points given by `No. Points`.
+	///
+	/// The variables of a truncated fastaccess file are incomplete, so these files still return
+	/// an error.
+	pub fn recover_raw_file<P: AsRef<Path>>(path_raw: P) -> Result<Self,ParseError> {
+		let mut steps = Vec::new();
+		let mut measurements = Measurements::default();
+		let mut variables = Vec::new();
+		let mut reals = Vec::new();
+		let mut imags = Vec::new();
+
+		let (header,expected) = read_raw_file_recovering(&path_raw,&mut variables,&mut reals,&mut imags)?;
+		let found = header.nr_of_points;
+		let path_log = path_raw.as_ref().with_extension("log");
+		if path_log.is_file() {
+			read_log_file(&path_log,&mut steps,&mut measurements)?;
+		}
+		if found == 0 && expected > 0 {
+			return Err(ParseError::PointCountMismatch {
+				expected: expected,
+				found: found
+			});
+		}
+		let truncation = if found < expected {
+			Some(Truncation {
+				expected: expected,
+				found: found
+			})
+		} else {
+			None
+		};
+		SteppedSimulation::from_parts(steps,measurements,variables,Columns::Loaded { reals: reals, imags: imags },header,truncation)
+	}
+
+	/// Opens the `.raw`-File like `from_raw_file`, but only reads the ASCII description and
+	/// the sweep variable, which is needed to find the steps.
+	///
+	/// The values of every other variable are read from the file, when they are accessed for
+	/// the first time (e.g. with `get_values_for_variable_at`), and kept afterwards. This saves
+	/// a lot of time and memory, if only a few variables of a large file are needed. Use
+	/// `load_variable` to read a variable in advance and to see, why it could not be read.
+	///
+	/// If a variable can not be read later on, the accessors like `get_values_at` return
+	/// empty results. Their `try_` counterparts (e.g. `try_get_values_at`) return the error instead.
+	pub fn open_lazy<P: AsRef<Path>>(path_raw: P) -> Result<Self,ParseError> {
+		let mut steps = Vec::new();
+		let mut measurements = Measurements::default();
+		let mut file = fs::File::open(&path_raw)?;
+		let (file_buf,text,data_offset,format) = read_header(&mut file)?;
+		let (header,variables) = parse_description(&text,&file_buf[data_offset..],format)?;
+		let path_log = path_raw.as_ref().with_extension("log");
+		if path_log.is_file() {
+			read_log_file(&path_log,&mut steps,&mut measurements)?;
+		}
+		let columns = Columns::Lazy {
+			path: path_raw.as_ref().to_path_buf(),
+			data_offset: data_offset,
+			format: format,
+			cache: Mutex::new((0..variables.len()).map(|_| None).collect())
+		};
+		SteppedSimulation::from_parts(steps,measurements,variables,columns,header,None)
+	}
+
+	/// Assigns the steps of the `.log`-File to the steps found in the data.
+	/// If there are no steps in the `.log`-File, the steps are numbered.
+	///
+	/// The data of a truncated file (see `recover_raw_file`) ends in the middle of the last
+	/// step, so the steps after the abort are dropped. `header` contains the number of points,
+	/// which were found.
+	fn from_parts(mut steps: Vec<Step>, mut measurements: Measurements, variables: Vec<SimulationVariable>, mut columns: Columns, mut header: RawHeader, truncation: Option<Truncation>) -> Result<Self,ParseError> {
+		let compressed_points = if header.plot_kind == PlotKind::Transient {
+			columns.normalise_time(&header)?
+		} else {
+			Vec::new()
+		};
+		// The columns must contain the number of points given by `No. Points`
+		let found = columns.with_column(&header,0,|reals,_| reals.len())?;
+		if found != header.nr_of_points {
+			return Err(ParseError::PointCountMismatch {
+				expected: header.nr_of_points,
+				found: found
+			});
+		}
+		let mut sweep_blocks = if header.plot_kind.has_sweep_variable() {
+			columns.with_column(&header,0,|reals,_| find_step_ranges(reals))?
+		} else {
+			// Without a sweep variable every step is a single point
+			(0..header.nr_of_points).map(|ct| StepRange { offset: ct, len: 1 }).collect()
+		};
+		if sweep_blocks.len() == 1 && steps.len() > 1 && header.nr_of_points % steps.len() == 0 && truncation.is_none() {
+			// Steps with a single point (e.g. `.ac list 1k`) can not be found in the sweep
+			// variable, so the points are divided equally among the steps of the `.log`-File
+			let len = header.nr_of_points / steps.len();
+			sweep_blocks = (0..steps.len()).map(|ct| StepRange { offset: ct * len, len: len }).collect();
+		}
+		let nested_sweep = header.plot_kind == PlotKind::DcSweep;
+		let from_log = !steps.is_empty();
+		if steps.is_empty() {
+			if nested_sweep && !header.flags.stepped {
+				// All blocks belong to the second source of a single `.dc` sweep
+				steps.push(Vec::new());
+			} else if sweep_blocks.len() > 1 {
+				for ct in 0..sweep_blocks.len() {
+					steps.push(vec![SteppingVariable {
+						name: String::from("step"),
+						value: (ct+1) as f64
+					}]);
+				}
+			} else {
+				steps.push(Vec::new());
+			}
+		}
+		// A `.dc` sweep with two sources has a block for every value of the second source in every step
+		let blocks_per_step = match truncation {
+			Some(truncation) if nested_sweep && from_log => {
+				// The blocks after the abort are missing, but all blocks have the same length
+				let len = sweep_blocks[0].len;
+				if len > 0 && truncation.expected % len == 0 { truncation.expected / len / steps.len() } else { 0 }
+			},
+			_ if nested_sweep => sweep_blocks.len() / steps.len(),
+			_ => 1
+		};
+		let nr_of_steps = match truncation {
+			// The last step ends at the abort, the steps after it are only part of the `.log`-File
+			Some(_) => (sweep_blocks.len() + blocks_per_step.max(1) - 1) / blocks_per_step.max(1),
+			None => sweep_blocks.len() / blocks_per_step.max(1)
+		};
+		if truncation.is_some() {
+			steps.truncate(nr_of_steps);
+		}
+		// Every block of a step sweeps the first source over the same values
+		let same_blocks = blocks_per_step > 0 && sweep_blocks.iter().enumerate().all(|(ct,block)| {
+			let first = sweep_blocks[ct - ct % blocks_per_step].len;
+			// The last block of a truncated file ends at the abort
+			block.len == first || (truncation.is_some() && ct + 1 == sweep_blocks.len() && block.len < first)
+		});
+		if steps.len() != nr_of_steps || steps.len() * blocks_per_step < sweep_blocks.len() || !same_blocks {
+			return Err(ParseError::StepCountMismatch {
+				blocks: sweep_blocks.len(),
+				steps: steps.len()
+			});
+		}
+		// The `.log`-File might contain more steps than the data (e.g. of a truncated file)
+		measurements.align_to_steps(steps.len());
+		let step_ranges: Vec<StepRange> = sweep_blocks.chunks(blocks_per_step).map(|blocks| StepRange {
+			offset: blocks[0].offset,
+			len: blocks.iter().map(|block| block.len).sum()
+		}).collect();
+		if let Some(truncation) = truncation {
+			header.nr_of_points = truncation.expected;
+		}
+		Ok(SteppedSimulation {
+			steps: steps,
+			variables: variables,
+			columns: columns,
+			header: header,
+			step_ranges: step_ranges,
+			sweep_blocks: sweep_blocks,
+			truncation: truncation,
+			measurements: measurements,
+			compressed_points: compressed_points
+		})
+	}
+
+
+	/// Reads the values of a single variable for all steps without keeping the rest of the file.
+	///
+	/// If the file was written with the fastaccess option, only the part of the file which
+	/// contains this variable is read from the disk. The result contains the real and the
+	/// imaginary parts of all points.
+	pub fn read_single_variable<P: AsRef<Path>>(path_raw: P, name: &str) -> Result<(Vec<f64>,Vec<f64>),ParseError> {
+		let mut file = fs::File::open(&path_raw)?;
+		let (file_buf,text,data_offset,format) = read_header(&mut file)?;
+		let (header,variables) = parse_description(&text,&file_buf[data_offset..],format)?;
+		let var_index = match variables.iter().position(|var| var.name == name) {
+			Some(var_index) => var_index,
+			None => return Err(ParseError::MissingVariable(name.to_owned()))
+		};
+		let (mut reals,imags) = read_column(&path_raw,&header,data_offset,format,var_index)?;
+		if var_index == 0 && header.plot_kind == PlotKind::Transient {
+			decode::normalise_time(&mut reals);
+		}
+		Ok((reals,imags))
+	}
+
+	/// Reads the values of a variable, if the simulation was opened with `open_lazy`.
+	///
+	/// The values are kept, so the file is only read once for every variable. If the
+	/// simulation was opened otherwise, all values are already available.
+	pub fn load_variable(&self, var: &SimulationVariable) -> Result<(),ParseError> {
+		match self.variables.iter().position(|r| r.eq(var)) {
+			Some(var_pos) => self.columns.with_column(&self.header,var_pos,|_,_| ()),
+			None => Err(ParseError::MissingVariable(var.name.clone()))
+		}
+	}
+
+	/// Returns the ASCII description of the `.raw`-File
+	pub fn header(&self) -> &RawHeader {
+		&self.header
+	}
+
+	/// Returns the number of expected and found points, if the file was read with
+	/// `recover_raw_file` and is truncated. Returns None for complete files.
+	pub fn truncation(&self) -> Option<Truncation> {
+		self.truncation
+	}
+
+	/// Returns the results of the `.meas` statements, which were read from the `.log`-File.
+	/// Every measurement has one value for every step of `available_steps`.
+	pub fn measurements(&self) -> &Measurements {
+		&self.measurements
+	}
+
+	/// Returns false for the last step of a truncated file, because the simulation was aborted
+	/// during this step. All other steps are complete. Returns false, if the step is not part
+	/// of this simulation.
+	pub fn is_step_complete(&self, step: &Step) -> bool {
+		match self.steps.iter().position(|r| r.eq(step)) {
+			Some(position) => self.truncation.is_none()
+				|| !self.header.plot_kind.has_sweep_variable()
+				|| position + 1 < self.steps.len(),
+			None => false
+		}
+	}
+
+	/// Returns the indices of all points of a step, which LTspice marked with a negative time.
+	///
+	/// LTspice marks some points of a `.tran` simulation (e.g. where the compression of the
+	/// waveform restarts) by storing the time as a negative value. The time, which is returned
+	/// by all other functions, is always the absolute value, so it increases within every step.
+	/// The indices count from the start of the step.
+	pub fn compressed_points(&self, step: &Step) -> Vec<usize> {
+		match self.step_range(step) {
+			Some(range) => self.compressed_points.iter()
+				.filter(|point| **point >= range.offset && **point < range.offset + range.len)
+				.map(|point| point - range.offset)
+				.collect(),
+			None => Vec::new()
+		}
+	}
 
 	/// Returns a vector with all `.param` parameters as strings.
     pub fn available_parameters(&self) -> Vec<String> {
@@ -102,75 +399,182 @@ impl SteppedSimulation {
     	}
     	None
     }
+	/// Returns the noise contributions of all devices of a `.noise` simulation.
+	///
+	/// These are all variables except the frequency, the total noise `V(onoise)` and `V(inoise)`
+	/// and the `gain`. For all other simulations the vector is empty.
+	pub fn noise_contributions(&self) -> Vec<&SimulationVariable> {
+		if self.header.plot_kind != PlotKind::Noise {
+			return Vec::new();
+		}
+		self.variables.iter().skip(1).filter(|variable| {
+			let name = variable.name.to_lowercase();
+			variable.var_type != VariableType::Gain && name != "v(onoise)" && name != "v(inoise)"
+		}).collect()
+	}
 	/// Returns a vector of all steps of the simulation
     pub fn available_steps(&self) -> &Vec<Step> {
         &self.steps
     }
 
-	/// Returns all VariableResults for one step
+	/// Returns the position of the step in the data of the simulation.
+	/// Returns None, if the step is not part of this simulation.
+	pub fn step_range(&self, step: &Step) -> Option<StepRange> {
+		self.steps.iter().position(|r| r.eq(step)).map(|position| self.step_ranges[position])
+	}
+
+	/// Returns the value of a variable with the given name at a given step.
+	///
+	/// This is meant for `.op` and `.tf` simulations, where every step consists of exactly one
+	/// point. For other simulations the value of the first point of the step is returned.
+	pub fn get_scalar_for_name(&self, step: &Step, name: &str) -> Option<f64> {
+		let var_pos = match self.variables.iter().position(|var| var.name == name) {
+			Some(var_pos) => var_pos,
+			None => return None
+		};
+		match self.step_range(step) {
+			Some(range) if range.len > 0 => self.columns.with_column(&self.header,var_pos,|reals,_| reals[range.offset]).ok(),
+			_ => None
+		}
+	}
+
+	/// Returns the values of all variables at a given step (see `get_scalar_for_name`).
+	///
+	/// Use the `var_type` of the variables to distinguish node voltages from device currents.
+	pub fn get_scalars_at(&self, step: &Step) -> Vec<(&SimulationVariable,f64)> {
+		self.try_get_scalars_at(step).unwrap_or_default()
+	}
+
+	/// Like `get_scalars_at`, but returns the reason, why the values are not available
+	pub fn try_get_scalars_at(&self, step: &Step) -> Result<Vec<(&SimulationVariable,f64)>,ParseError> {
+		let range = self.try_step_range(step)?;
+		if range.len == 0 {
+			return Ok(Vec::new());
+		}
+		self.variables.iter()
+			.enumerate()
+			.map(|(var_pos,var)| self.columns.with_column(&self.header,var_pos,|reals,_| (var,reals[range.offset])))
+			.collect()
+	}
+
+	/// Returns all VariableResults for one step in the order of `available_variables`
     pub fn get_values_at(&self, step: &Step) -> Vec<VariableResult> {
-        match self.steps.iter().position(|r| r.eq(step)) {
-            Some(position) =>self.get_value_block_at(position),
-            None => Vec::new()
-        }
+        self.try_get_values_at(step).unwrap_or_default()
+    }
+
+	/// Like `get_values_at`, but returns the reason, why the values are not available
+    pub fn try_get_values_at(&self, step: &Step) -> Result<Vec<VariableResult>,ParseError> {
+        let range = self.try_step_range(step)?;
+        self.get_value_block(range)
     }
 
 
 	/// Returns the VariableResult for one variable at a given step
     pub fn get_values_for_variable_at(&self, step: &Step, var: &SimulationVariable) -> Option<VariableResult> {
+        self.try_get_values_for_variable_at(step,var).ok()
+    }
 
-        match self.steps.iter().position(|r| r.eq(step)) {
-            Some(position) =>{
-                match self.variables.iter().position(|r| r.eq(var)) {
-                    Some(var_pos) =>{
+	/// Like `get_values_for_variable_at`, but returns the reason, why the values are not available
+    pub fn try_get_values_for_variable_at(&self, step: &Step, var: &SimulationVariable) -> Result<VariableResult,ParseError> {
+        let range = self.try_step_range(step)?;
+        self.read_values_for_variable_in(range,var)
+    }
 
-                        let variable_result = self.get_value_block_at(position);
-                        Some(variable_result[var_pos].clone())
-                    },
-                    None => None
-                }
+	/// Returns the position of the step or `MissingStep`, if the step is not part of this simulation
+    fn try_step_range(&self, step: &Step) -> Result<StepRange,ParseError> {
+        self.step_range(step).ok_or_else(|| ParseError::MissingStep(step.clone()))
+    }
 
-            },
-            None => None
+	/// Returns the values of a variable in the given range or the reason, why they are not available
+    fn read_values_for_variable_in(&self, range: StepRange, var: &SimulationVariable) -> Result<VariableResult,ParseError> {
+        match self.variables.iter().position(|r| r.eq(var)) {
+            Some(var_pos) => self.get_variable_block(var_pos,range),
+            None => Err(ParseError::MissingVariable(var.name.clone()))
         }
     }
 	
 	
-    fn get_value_block_at(&self, pos:usize) -> Vec<VariableResult> {
-        let start:usize = pos * self.points_per_block;
-        let mut var_count = 0;
-        let mut result = Vec::new();
-        for v in &self.variables {
-            let local_reals = &self.reals[var_count];
-            let local_imags = &self.imags[var_count];
-            result.push(VariableResult::new(v,local_reals[start .. (start+self.points_per_block)].to_vec(),local_imags[start..(start+self.points_per_block)].to_vec()));
+	/// Returns the variable, which is swept by the simulation (e.g. the time, the frequency or
+	/// the source of a `.dc` sweep). `.op` and `.tf` simulations have no sweep variable.
+	pub fn sweep_variable(&self) -> Option<&SimulationVariable> {
+		if self.header.plot_kind.has_sweep_variable() {
+			self.variables.first()
+		} else {
+			None
+		}
+	}
+
+	/// Returns all VariableResults for one step, split into the blocks of a nested sweep.
+	///
+	/// A `.dc` sweep with two sources (e.g. `.dc V1 0 5 0.1 V2 0 1 0.5`) sweeps the first
+	/// source once for every value of the second source. Every block contains one of these
+	/// runs. For all other simulations the step consists of a single block.
+	pub fn get_sub_blocks_at(&self, step: &Step) -> Vec<Vec<VariableResult>> {
+		self.try_get_sub_blocks_at(step).unwrap_or_default()
+	}
+
+	/// Like `get_sub_blocks_at`, but returns the reason, why the values are not available
+	pub fn try_get_sub_blocks_at(&self, step: &Step) -> Result<Vec<Vec<VariableResult>>,ParseError> {
+		let range = self.try_step_range(step)?;
+		self.sub_blocks_in(range)
+			.map(|block| self.get_value_block(*block))
+			.collect()
+	}
+
+	/// Returns the VariableResult for one variable at a given step for every block of a
+	/// nested sweep (see `get_sub_blocks_at`)
+	pub fn get_sub_blocks_for_variable_at(&self, step: &Step, var: &SimulationVariable) -> Vec<VariableResult> {
+		self.try_get_sub_blocks_for_variable_at(step,var).unwrap_or_default()
+	}
+
+	/// Like `get_sub_blocks_for_variable_at`, but returns the reason, why the values are not available
+	pub fn try_get_sub_blocks_for_variable_at(&self, step: &Step, var: &SimulationVariable) -> Result<Vec<VariableResult>,ParseError> {
+		let range = self.try_step_range(step)?;
+		self.sub_blocks_in(range)
+			.map(|block| self.read_values_for_variable_in(*block,var))
+			.collect()
+	}
+
+	/// Returns the blocks of the sweep variable, which belong to the given step
+	fn sub_blocks_in<'a>(&'a self, range: StepRange) -> impl Iterator<Item=&'a StepRange> + 'a {
+		self.sweep_blocks.iter()
+			.filter(move |block| block.offset >= range.offset && block.offset < range.offset + range.len)
+	}
+
+    /// Returns the values of all variables in the given range. Returns an error, if one of the
+    /// variables of a lazily opened simulation could not be read.
+    fn get_value_block(&self, range: StepRange) -> Result<Vec<VariableResult>,ParseError> {
+        (0..self.variables.len()).map(|var_pos| self.get_variable_block(var_pos,range)).collect()
+    }
 
-            var_count += 1;
-        }
-        result
+    /// Returns the values of one variable in the given range. Returns an error, if the values
+    /// of a lazily opened simulation could not be read.
+    fn get_variable_block(&self, var_pos: usize, range: StepRange) -> Result<VariableResult,ParseError> {
+        let start = range.offset;
+        let end = start + range.len;
+        self.columns.with_column(&self.header,var_pos,|reals,imags| {
+            VariableResult::new(&self.variables[var_pos],reals[start..end].to_vec(),imags[start..end].to_vec())
+        })
     }
     
    
 	/// Calculates the fitnesses of all steps for a given variable. 
 	/// The fitness is determined by the internal fitness function of the VariableResult.
 	/// Currently it is not possible to define the fitness function by yourself.
-    pub fn calculate_fitnesses(&self, var: &SimulationVariable) -> Vec<f64> {
+    pub fn calculate_fitnesses(&self, var: &SimulationVariable) -> Result<Vec<f64>,ParseError> {
         let mut fitnesses = Vec::new();
-        let (averages,deviations) = self.find_averages_for_fitness(&var);
-        for step in &self.steps {
-            let freq = self.get_values_for_variable_at(&step,&self.variables[0]).unwrap();
-            let values = self.get_values_for_variable_at(&step,&var);
-            let fitness = match values {
-                Some(vl) => vl.calculate_fitness(&freq,averages,deviations),
-                None => panic!("wooow")
-            };
+        let (averages,deviations) = self.find_averages_for_fitness(&var)?;
+        for range in &self.step_ranges {
+            let freq = self.read_values_for_variable_in(*range,&self.variables[0])?;
+            let values = self.read_values_for_variable_in(*range,&var)?;
+            let fitness = values.calculate_fitness(&freq,averages,deviations)?;
             fitnesses.push(fitness.1);
          
         }
-        fitnesses
+        Ok(fitnesses)
     }
     
-    pub fn find_averages_for_fitness(&self,var: &SimulationVariable) -> ([f64;5],[f64;5]) {
+    pub fn find_averages_for_fitness(&self,var: &SimulationVariable) -> Result<([f64;5],[f64;5]),ParseError> {
     	let mut result = [0.0;5];
     	let mut deviations = [0.0;5];
     	
@@ -180,22 +584,17 @@ impl SteppedSimulation {
     	let mut most_rights = Vec::new();
     	let mut at_100_hz = Vec::new();
     	
-    	let freq = self.get_values_for_variable_at(&self.steps[0],&self.variables[0]).unwrap();
-    	let (index_one_k,_) = self.get_values_for_variable_at(&self.steps[0],&var).unwrap().find_value_near_freq(&DataType::AbsoluteDecibel,&freq, 1000.0);
-    	let (index_one_h,_) = self.get_values_for_variable_at(&self.steps[0],&var).unwrap().find_value_near_freq(&DataType::AbsoluteDecibel,&freq, 100.0);
-    	for step in &self.steps {
-    		
-            let values = self.get_values_for_variable_at(&step,&var);
-            match values {
-            	Some(values) =>{
-		    		avgs.push(values.avg_normalized(&DataType::AbsoluteDecibel,&freq));
-		    		min_maxs.push(1.0/(values.max(&DataType::AbsoluteDecibel).1-values.min(&DataType::AbsoluteDecibel).1));
-		    		most_lefts.push(values.get_data_point(&DataType::AbsoluteDecibel,0).unwrap());
-		    		most_rights.push(values.get_data_point(&DataType::AbsoluteDecibel,index_one_k).unwrap());
-		    		at_100_hz.push(values.get_data_point(&DataType::AbsoluteDecibel,index_one_h).unwrap());
-            	},
-            	None => panic!("Nicht vorhanden")
-            }
+    	for range in &self.step_ranges {
+    		// The steps may have different frequencies, so they are searched in every step
+            let freq = self.read_values_for_variable_in(*range,&self.variables[0])?;
+            let values = self.read_values_for_variable_in(*range,&var)?;
+            let (_,one_k_value) = values.find_value_near_freq(&DataType::AbsoluteDecibel,&freq, 1000.0).ok_or(ParseError::FrequencyNotFound(1000.0))?;
+            let (_,one_h_value) = values.find_value_near_freq(&DataType::AbsoluteDecibel,&freq, 100.0).ok_or(ParseError::FrequencyNotFound(100.0))?;
+    		avgs.push(values.avg_normalized(&DataType::AbsoluteDecibel,&freq));
+    		min_maxs.push(1.0/(values.max(&DataType::AbsoluteDecibel).1-values.min(&DataType::AbsoluteDecibel).1));
+    		most_lefts.push(values.get_data_point(&DataType::AbsoluteDecibel,0).ok_or(ParseError::FrequencyNotFound(0.0))?);
+    		most_rights.push(one_k_value);
+    		at_100_hz.push(one_h_value);
     	}
     	result[0]=mean(&avgs);
     	deviations[0] = standard_deviation(&avgs,Some(result[0]));
@@ -207,21 +606,27 @@ impl SteppedSimulation {
     	deviations[3] = standard_deviation(&most_rights,Some(result[3]));
     	result[4]=mean(&at_100_hz);
     	deviations[4] = standard_deviation(&at_100_hz,Some(result[4]));
-    	(result,deviations)
+    	Ok((result,deviations))
     }
 
 
 	/// Finds all VariableResults, that contain a resonance at the given frequency.
 	/// This algorithm is not very good at the current moment, as it just takes the frequency ticks with a distance of 2
 	/// to determine the borders of the allowed frame.
-    pub fn find_with_resonance_at(&self,  var: &SimulationVariable, res_freq: f64) -> Vec<VariableResult> {
-        let freq = self.get_values_for_variable_at(&self.steps[0],&self.variables[0]).unwrap();
-        let freq_index = freq.get_data(&DataType::Real).iter().position(|b| b>&res_freq).unwrap();
-        let fq_barrier_low = freq_index-2;
-        let fq_barrier_high = freq_index+2;
+	///
+	/// Steps, whose frequencies are all lower than the given frequency, are skipped.
+    pub fn find_with_resonance_at(&self,  var: &SimulationVariable, res_freq: f64) -> Result<Vec<VariableResult>,ParseError> {
         let mut result = Vec::new();
-        for step in &self.steps {
-            let values = self.get_values_for_variable_at(&step,&var).unwrap();
+        for range in &self.step_ranges {
+            let freq = self.read_values_for_variable_in(*range,&self.variables[0])?;
+            let values = self.read_values_for_variable_in(*range,&var)?;
+            // The steps may have different frequencies, so they are searched in every step
+            let freq_index = match freq.get_data(&DataType::Real).iter().position(|b| b>&res_freq) {
+                Some(freq_index) => freq_index,
+                None => continue
+            };
+            let fq_barrier_low = freq_index.saturating_sub(2);
+            let fq_barrier_high = freq_index+2;
             let peaks =  values.find_peaks(Some(PeakType::Maximum),&DataType::AbsoluteDecibel);
             for peak in peaks {
                 if peak > fq_barrier_low && peak < fq_barrier_high {
@@ -229,7 +634,7 @@ impl SteppedSimulation {
                 }
             }
         }
-        result
+        Ok(result)
 
     }
 
@@ -239,152 +644,392 @@ impl SteppedSimulation {
 }
 
 
-fn read_log_file<P: AsRef<Path>>(path: P,steps: &mut Vec<Step>) -> i32 {
-    let file = fs::File::open(path).unwrap();
-    let mut file = BufReader::new(file);
-    let mut line = String::new();
+fn read_log_file<P: AsRef<Path>>(path: P,steps: &mut Vec<Step>,measurements: &mut Measurements) -> Result<i32,ParseError> {
+    let mut file = fs::File::open(path)?;
+    let mut file_buf = Vec::new();
+    file.read_to_end(&mut file_buf)?;
+    let text = encoding::decode_text(&file_buf);
     let mut step_counter = 0;
-    while file.read_line(&mut line).unwrap() > 0 {
+    for line in text.lines() {
         if line.starts_with(".step") {
             step_counter += 1;
             let mut k = line.split_whitespace();
             k.next();
             let mut step:Step = Vec::new();
             while let Some(var_text) = k.next() {
-                let var = SteppingVariable::new(var_text);
+                let var = SteppingVariable::new(var_text)?;
                 step.push(var);
             }
             steps.push(step);
         }
-        line.clear();
     }
-    step_counter
+    *measurements = Measurements::parse(&text,steps.len());
+    Ok(step_counter)
 }
 
 
-fn read_raw_file<P: AsRef<Path>>(path: P, variables: &mut Vec<SimulationVariable>, reals: &mut Vec<Vec<f64>>,imags: &mut Vec<Vec<f64>>) -> i32 {
-    let nr_of_points = read_description(&path,variables);
-    for _ in 0..variables.len() {
-        reals.push(Vec::new());
-        imags.push(Vec::new());
-    }
-    get_values(&path,reals,imags);
-
-    nr_of_points
+/// All plots of a `.raw`-File
+///
+/// ngspice (and sometimes LTspice) writes several plots into one file, e.g. the operating
+/// point followed by an `.ac` analysis. Every plot has its own description and variables.
+/// `SteppedSimulation::from_raw_file` only reads the first plot of such a file.
+///
+/// # Examples
+/// ```no_run
+/// # use ltspice_parse::RawFile;
+/// let file = RawFile::from_file("ngspice.raw").unwrap();
+/// let op = file.plot(0).unwrap();
+/// let ac = file.plot_for_name("AC Analysis").unwrap();
+/// ```
+#[derive(Debug)]
+pub struct RawFile {
+    plots: Vec<SteppedSimulation>
 }
 
+impl RawFile {
+    /// Reads all plots of the `.raw`-File.
+    ///
+    /// The steps of the plots are found like in `SteppedSimulation::from_raw_file`. The
+    /// parameters of the steps are read from a `.log`-File with the same name, if there is
+    /// one. They are used for all plots with the flag `stepped`.
+    pub fn from_file<P: AsRef<Path>>(path_raw: P) -> Result<Self,ParseError> {
+        let mut file = fs::File::open(&path_raw)?;
+        let mut file_buf = Vec::new();
+        file.read_to_end(&mut file_buf)?;
+        let mut log_steps = Vec::new();
+        let mut measurements = Measurements::default();
+        let path_log = path_raw.as_ref().with_extension("log");
+        if path_log.is_file() {
+            read_log_file(&path_log,&mut log_steps,&mut measurements)?;
+        }
 
+        let mut plots = Vec::new();
+        let mut pos = 0;
+        // Only line breaks (or zero bytes of UTF-16) follow the last plot
+        while file_buf[pos..].iter().any(|byte| !byte.is_ascii_whitespace() && *byte != 0) {
+            let mut variables = Vec::new();
+            let mut reals = Vec::new();
+            let mut imags = Vec::new();
+            let (header,len) = read_plot(&file_buf[pos..],&mut variables,&mut reals,&mut imags)?;
+            pos += len;
+            let steps = if header.flags.stepped { log_steps.clone() } else { Vec::new() };
+            plots.push(SteppedSimulation::from_parts(steps,measurements.clone(),variables,Columns::Loaded { reals: reals, imags: imags },header,None)?);
+        }
+        if plots.is_empty() {
+            return Err(ParseError::MalformedHeader(String::from("no plot found")));
+        }
+        Ok(RawFile {
+            plots: plots
+        })
+    }
 
-#[derive(PartialEq)]
-enum DescriptionState {
-    VarLength,
-    VarList,
-    Binary,
-    NrOfPoints,
-    Other
-}
+    /// Returns all plots in the order of the file
+    pub fn plots(&self) -> &Vec<SteppedSimulation> {
+        &self.plots
+    }
+
+    /// Returns the plot with the given index. Returns None, if there are less plots.
+    pub fn plot(&self, index: usize) -> Option<&SteppedSimulation> {
+        self.plots.get(index)
+    }
 
+    /// Returns the first plot, which has the given `Plotname:` (e.g. `AC Analysis`).
+    /// Returns None, if there is no such plot.
+    pub fn plot_for_name(&self, plot_name: &str) -> Option<&SteppedSimulation> {
+        self.plots.iter().find(|plot| plot.header.plot_name == plot_name.trim())
+    }
 
-fn read_description<P: AsRef<Path>>(path: P, variables: &mut Vec<SimulationVariable>) -> i32 {
-    let file = fs::File::open(path).unwrap();
+    /// Returns all plots
+    pub fn into_plots(self) -> Vec<SteppedSimulation> {
+        self.plots
+    }
+}
 
-    let mut file = BufReader::new(file);
-    let mut state = DescriptionState::Other;
+/// The values of all variables of a simulation
+#[derive(Debug)]
+enum Columns {
+    /// All values were read, when the simulation was opened
+    Loaded {
+        reals: Vec<Vec<f64>>,
+        imags: Vec<Vec<f64>>
+    },
+    /// The values of every variable are read on the first access (see `SteppedSimulation::open_lazy`)
+    Lazy {
+        path: PathBuf,
+        data_offset: usize,
+        format: DataFormat,
+        /// The real and imaginary parts of every variable, which was already read
+        cache: Mutex<Vec<Option<(Vec<f64>,Vec<f64>)>>>
+    }
+}
 
-    let mut nr_of_points = 0;
-    while state != DescriptionState::Binary {
-        let mut line:Vec<u8> = Vec::new();
-        file.read_until(b'\n',&mut line).unwrap();
-        let line = String::from_utf8(line).unwrap();
-        if line.contains("No. Variables") {
-            state = DescriptionState::VarLength;
-        }
-        else if line.contains("Variables") {
-            state = DescriptionState::VarList;
-        }
-        else if line.contains("Binary") {
-            state = DescriptionState::Binary;
+impl Columns {
+    /// Calls `f` with the real and imaginary parts of the variable with the given index.
+    /// In lazy mode the variable is read from the file first, if it is not cached yet.
+    fn with_column<T,F>(&self, header: &RawHeader, var_index: usize, f: F) -> Result<T,ParseError>
+        where F: FnOnce(&[f64],&[f64]) -> T {
+        match *self {
+            Columns::Loaded { ref reals, ref imags } => Ok(f(&reals[var_index],&imags[var_index])),
+            Columns::Lazy { ref path, data_offset, format, ref cache } => {
+                let mut cache = cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                if cache[var_index].is_none() {
+                    match format {
+                        DataFormat::Ascii(_) => {
+                            // ASCII data can only be decoded completely, so all variables are cached at once
+                            let mut reals = Vec::new();
+                            let mut imags = Vec::new();
+                            read_raw_file(path,&mut Vec::new(),&mut reals,&mut imags)?;
+                            for (column,values) in cache.iter_mut().zip(reals.into_iter().zip(imags.into_iter())) {
+                                // Cached variables are kept, because the time might be normalised
+                                if column.is_none() {
+                                    *column = Some(values);
+                                }
+                            }
+                        },
+                        DataFormat::Binary => cache[var_index] = Some(read_column(path,header,data_offset,format,var_index)?)
+                    }
+                }
+                match cache[var_index] {
+                    Some((ref reals,ref imags)) => Ok(f(reals,imags)),
+                    None => unreachable!()
+                }
+            }
         }
-        else if line.contains("No. Points") {
-            state = DescriptionState::NrOfPoints;
-            let cols:Vec<&str> = line.split(':').collect();
-            nr_of_points = i32::from_str(cols[1].trim()).unwrap();
+    }
+}
+
+impl Columns {
+    /// Replaces negative values of the time of a `.tran` plot by their absolute value and
+    /// returns the indices of these points (see `decode::normalise_time`)
+    fn normalise_time(&mut self, header: &RawHeader) -> Result<Vec<usize>,ParseError> {
+        // A lazy simulation needs the time in its cache
+        self.with_column(header,0,|_,_| ())?;
+        match *self {
+            Columns::Loaded { ref mut reals, .. } => Ok(decode::normalise_time(&mut reals[0])),
+            Columns::Lazy { ref mut cache, .. } => {
+                let cache = cache.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner());
+                match cache[0] {
+                    Some((ref mut reals,_)) => Ok(decode::normalise_time(reals)),
+                    None => unreachable!()
+                }
+            }
         }
-        match state {
-            DescriptionState::VarList => {
-                let cols:Vec<&str> = line.split('\t').collect();
-                if cols.len() == 4 {
-                    let vt = match cols[3] {
-                        "frequency" => VariableType::Frequency,
-                        "voltage" => VariableType::Voltage,
-                        "device_current" => VariableType::Current,
-                        _ => VariableType::Unknown
-                    };
-                    variables.push(SimulationVariable {
-                        id:u16::from_str(cols[1]).unwrap(),
-                        name:String::from(cols[2]),
-                        var_type:vt
+    }
+}
+
+/// Reads the values of one variable from the `.raw`-File.
+///
+/// Files with fastaccess only read the part of the file, which contains the variable. Other
+/// binary files are read point by point without keeping the other variables. ASCII data is
+/// decoded completely.
+fn read_column<P: AsRef<Path>>(path: P, header: &RawHeader, data_offset: usize, format: DataFormat, var_index: usize) -> Result<(Vec<f64>,Vec<f64>),ParseError> {
+    let mut file = fs::File::open(&path)?;
+    match format {
+        DataFormat::Binary if header.flags.fastaccess => {
+            let size = decode::sample_size(header,var_index) * header.nr_of_points;
+            let mut column_buf = Vec::with_capacity(size);
+            file.seek(SeekFrom::Start((data_offset + decode::column_offset(header,var_index)) as u64))?;
+            file.take(size as u64).read_to_end(&mut column_buf)?;
+            decode::decode_column(&column_buf,header,var_index)
+        },
+        DataFormat::Binary => {
+            let point_size = decode::point_size(header);
+            let in_point: usize = (0..var_index).map(|index| decode::sample_size(header,index)).sum();
+            let mut point_buf = vec![0u8;point_size];
+            let mut reals = Vec::with_capacity(header.nr_of_points);
+            let mut imags = Vec::with_capacity(header.nr_of_points);
+            file.seek(SeekFrom::Start(data_offset as u64))?;
+            let mut reader = BufReader::new(file);
+            for point in 0..header.nr_of_points {
+                if reader.read_exact(&mut point_buf).is_err() {
+                    return Err(ParseError::TruncatedBinary {
+                        expected: point_size * header.nr_of_points,
+                        found: point_size * point
                     });
                 }
-
+                let (real,imag) = decode::read_sample(&point_buf[in_point..],header,var_index);
+                reals.push(real);
+                imags.push(imag);
             }
-            _ => {},
+            Ok((reals,imags))
+        },
+        DataFormat::Ascii(_) => {
+            let mut reals = Vec::new();
+            let mut imags = Vec::new();
+            read_raw_file(path,&mut Vec::new(),&mut reals,&mut imags)?;
+            Ok((reals.swap_remove(var_index),imags.swap_remove(var_index)))
         }
     }
-
-    nr_of_points
-
 }
 
+/// Finds the steps in the values of the sweep variable.
+///
+/// A new step starts, whenever the sweep variable returns to the value of the first point.
+fn find_step_ranges(sweep: &[f64]) -> Vec<StepRange> {
+    let mut step_ranges = Vec::new();
+    let mut offset = 0;
+    for ct in 1..sweep.len() {
+        if sweep[ct] == sweep[0] && sweep[ct-1] != sweep[0] {
+            step_ranges.push(StepRange {
+                offset: offset,
+                len: ct - offset
+            });
+            offset = ct;
+        }
+    }
+    step_ranges.push(StepRange {
+        offset: offset,
+        len: sweep.len() - offset
+    });
+    step_ranges
+}
 
 
-
-fn get_values<P: AsRef<Path>>(path: P,reals: &mut Vec<Vec<f64>>,imags: &mut Vec<Vec<f64>>)  {
-
-    let mut file = fs::File::open(path).unwrap();
+fn read_raw_file<P: AsRef<Path>>(path: P, variables: &mut Vec<SimulationVariable>, reals: &mut Vec<Vec<f64>>,imags: &mut Vec<Vec<f64>>) -> Result<RawHeader,ParseError> {
+    let mut file = fs::File::open(path)?;
     let mut file_buf = Vec::new();
-    let mut freq_step_counter = 0;
-    let mut ct = 0;
-    //let mut line_ct = 0;
-    let mut in_binary = false;
-    file.read_to_end(&mut file_buf).unwrap();
-
-    let mut buffer = Vec::new();
-    while ct<(file_buf.len()) {
-        if !in_binary {
-            //Wait for binary
-            let k = &[file_buf[ct].clone()];
-
-            buffer.push(k[0]);
-            if String::from_utf8(buffer.clone()).unwrap().contains("Binary:\n") {
-                in_binary = true;
-
-            }
+    file.read_to_end(&mut file_buf)?;
 
+    let (header,_) = read_plot(&file_buf,variables,reals,imags)?;
+    Ok(header)
+}
 
-            ct+=1;
+/// Reads the first plot in the buffer.
+///
+/// Returns the header and the number of bytes of the plot. The next plot of the file starts
+/// after these bytes.
+fn read_plot(file_buf: &[u8], variables: &mut Vec<SimulationVariable>, reals: &mut Vec<Vec<f64>>,imags: &mut Vec<Vec<f64>>) -> Result<(RawHeader,usize),ParseError> {
+    let (text,data_offset,format) = split_header(file_buf)?;
+    let (header,header_variables) = parse_description(&text,&file_buf[data_offset..],format)?;
+    *variables = header_variables;
+    for _ in 0..variables.len() {
+        reals.push(Vec::new());
+        imags.push(Vec::new());
+    }
+    let end = match format {
+        DataFormat::Binary => {
+            decode::decode_binary(&file_buf[data_offset..],&header,reals,imags)?;
+            data_offset + decode::point_size(&header) * header.nr_of_points
+        },
+        DataFormat::Ascii(encoding) => {
+            // The values end, where the next plot starts
+            let end = match encoding.find(&file_buf[data_offset..],"\nTitle:") {
+                Some(pos) => data_offset + pos + encoding.char_width(),
+                None => file_buf.len()
+            };
+            decode::decode_ascii(&file_buf[data_offset..end],encoding,&header,reals,imags)?;
+            end
         }
-        else {
+    };
 
+    Ok((header,end))
+}
 
-            let start = ct;
-            let end = start + 8;
+/// Reads all complete points of a `.raw`-File, which might be truncated.
+///
+/// `No. Points` of the returned header is the number of points, which were read. The number
+/// of points given in the file is returned as well.
+fn read_raw_file_recovering<P: AsRef<Path>>(path: P, variables: &mut Vec<SimulationVariable>, reals: &mut Vec<Vec<f64>>,imags: &mut Vec<Vec<f64>>) -> Result<(RawHeader,usize),ParseError> {
+    let mut file = fs::File::open(path)?;
+    let mut file_buf = Vec::new();
+    file.read_to_end(&mut file_buf)?;
 
-            let real = LittleEndian::read_f64(&file_buf[start..end]);
-            let imag = LittleEndian::read_f64(&file_buf[end..end+8]);
+    let (text,data_offset,format) = split_header(&file_buf)?;
+    let (mut header,header_variables) = parse_description(&text,&file_buf[data_offset..],format)?;
+    let expected = header.nr_of_points;
+    *variables = header_variables;
+    for _ in 0..variables.len() {
+        reals.push(Vec::new());
+        imags.push(Vec::new());
+    }
+    match format {
+        DataFormat::Binary => {
+            if !header.flags.fastaccess {
+                let complete = (file_buf.len() - data_offset) / decode::point_size(&header);
+                header.nr_of_points = expected.min(complete);
+            }
+            decode::decode_binary(&file_buf[data_offset..],&header,reals,imags)?;
+        },
+        DataFormat::Ascii(encoding) => match decode::decode_ascii(&file_buf[data_offset..],encoding,&header,reals,imags) {
+            Ok(()) => {},
+            Err(ParseError::TruncatedValues { found, .. }) => {
+                // The variables of the last point, which were read, are dropped
+                header.nr_of_points = found;
+                for column in reals.iter_mut().chain(imags.iter_mut()) {
+                    column.truncate(found);
+                }
+            },
+            Err(err) => return Err(err)
+        }
+    }
 
+    Ok((header,expected))
+}
 
-            ct+=16;
-            reals[freq_step_counter].push(real);
-            imags[freq_step_counter].push(imag);
+/// Parses the ASCII description. For binary ngspice files the byte order is guessed from
+/// the data, which follows the description (see `decode::detect_big_endian`).
+fn parse_description(text: &str, data: &[u8], format: DataFormat) -> Result<(RawHeader,Vec<SimulationVariable>),ParseError> {
+    let (mut header,variables) = RawHeader::parse(text)?;
+    if format == DataFormat::Binary {
+        header.big_endian = decode::detect_big_endian(data,&header);
+    }
+    Ok((header,variables))
+}
 
-            freq_step_counter = (freq_step_counter+1)%reals.len();
-            //line_ct+=1;
+/// Reads the file until the complete ASCII description is in the buffer.
+///
+/// Returns the buffer (which might already contain some of the data) together with the
+/// results of `split_header`.
+fn read_header<R: Read>(reader: &mut R) -> Result<(Vec<u8>,String,usize,DataFormat),ParseError> {
+    let mut file_buf = Vec::new();
+    let mut chunk = [0u8;4096];
+    loop {
+        let read = reader.read(&mut chunk)?;
+        file_buf.extend_from_slice(&chunk[..read]);
+        match split_header(&file_buf) {
+            Ok((header,data_offset,format)) => return Ok((file_buf,header,data_offset,format)),
+            Err(err) => if read == 0 {
+                return Err(err);
+            }
         }
-
-
     }
+}
 
 
+/// The way the values are stored after the ASCII description
+#[derive(Debug,Clone,Copy,PartialEq)]
+enum DataFormat {
+    /// The values follow "Binary:" as little endian floating point numbers
+    Binary,
+    /// The values follow "Values:" as text in the encoding of the description
+    Ascii(Encoding)
+}
+
+/// Splits the file into the ASCII description and the data.
+///
+/// The description is returned as a String, regardless of the encoding of the file.
+/// The second value is the position of the first byte after "Binary:\n" or "Values:\n".
+fn split_header(file_buf: &[u8]) -> Result<(String,usize,DataFormat),ParseError> {
+    let (encoding,bom) = Encoding::detect(file_buf);
+    let binary = encoding.find(&file_buf[bom..],"\nBinary:");
+    // Only search the description for "Values:", the binary data might be very large
+    let values = encoding.find(&file_buf[bom..bom + binary.unwrap_or(file_buf.len() - bom)],"\nValues:");
+    let (marker,format) = match (binary,values) {
+        (Some(b),Some(v)) if v < b => (v,DataFormat::Ascii(encoding)),
+        (Some(b),_) => (b,DataFormat::Binary),
+        (None,Some(v)) => (v,DataFormat::Ascii(encoding)),
+        (None,None) => return Err(ParseError::MalformedHeader(String::from("no \"Binary:\" or \"Values:\" line found")))
+    };
+    // Skip the newline in front of the marker
+    let marker = marker + bom + encoding.char_width();
+    // The line might end with "\r\n", so the data starts after the next newline
+    let newline = match encoding.find(&file_buf[marker..],"\n") {
+        Some(pos) => pos + marker,
+        None => return Err(ParseError::MalformedHeader(encoding.decode(&file_buf[marker..])))
+    };
+    let data_offset = newline + encoding.char_width();
+    Ok((encoding.decode(&file_buf[bom..data_offset]),data_offset,format))
 }
 
+
+
@@ -34,7 +34,7 @@ fn main() {
 	
     let results = SteppedSimulation::from_files(
     	path.with_extension("raw").as_path(),
-		path.with_extension("log").as_path());
+		path.with_extension("log").as_path()).unwrap();
 
     
     let vars = results.available_variables();
@@ -64,7 +64,7 @@ fn main() {
 	let vout = results.get_variable_for_name("V(vout)").unwrap();
 	
 	println!("Calculating Fitnesses");
-    let fit = results.calculate_fitnesses(&vout);
+    let fit = results.calculate_fitnesses(&vout).unwrap();
     let mut bundle:Vec<(usize,f64)> = Vec::new();
     for ct in 0..fit.len() {
         bundle.push((ct,fit[ct]));
@@ -73,7 +73,7 @@ fn main() {
     println!("Done with Fitnesses");
     let steps = results.available_steps();
     //println!("Fitnesses {:#?}", bundle);
-	let (averages,deviations) =results.find_averages_for_fitness(&vout);
+	let (averages,deviations) =results.find_averages_for_fitness(&vout).unwrap();
     let colors = vec!("black","green","yellow","blue","magenta");
     let mut ohzs = Vec::new();
     let mut khzs = Vec::new();
@@ -103,7 +103,7 @@ fn main() {
 	        
 	        
 	        println!("Winner:");
-	        let (single,sum,original) = values.calculate_fitness(&freq,averages,deviations);
+	        let (single,sum,original) = values.calculate_fitness(&freq,averages,deviations).unwrap();
 	        println!("Single Values are {:?}. Sum is {:?}", single, sum);
 	        ohzs.push(original[2]);
 	        khzs.push(original[3]);
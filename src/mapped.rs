@@ -0,0 +1,234 @@
+//! A memory mapped reader for large binary `.raw`-Files
+//!
+//! `SteppedSimulation` decodes every value of the file into memory. For files with several
+//! gigabytes this takes long and needs a lot of RAM. A `MappedSimulation` maps the file into
+//! memory instead and only locates the ASCII description and the steps. The values of a
+//! variable are available as a `Trace`, which converts the bytes of the file on demand.
+//!
+//! The file must not be changed, while it is mapped. Use `incremental::IncrementalReader` for
+//! files, which are still written by a running simulation.
+//!
+//! # Examples
+//! ```no_run
+//! # use ltspice_parse::mapped::MappedSimulation;
+//! // The simulation is finished, so nobody writes to the file
+//! let simulation = unsafe { MappedSimulation::open("Draft2.raw").unwrap() };
+//! let vout = simulation.get_variable_for_name("V(vout)").unwrap();
+//! let trace = simulation.trace_at(0,vout).unwrap();
+//! let max = trace.iter().map(|(real,_)| real).fold(::std::f64::MIN,f64::max);
+//! ```
+use std::fs;
+use std::path::Path;
+
+use memmap::Mmap;
+
+use decode;
+use error::ParseError;
+use header::{PlotKind, RawHeader};
+use results::{SimulationVariable, StepRange, VariableResult};
+use {find_step_ranges, parse_description, read_header, DataFormat};
+
+
+/// A binary `.raw`-File, which is mapped into memory
+#[derive(Debug)]
+pub struct MappedSimulation {
+    map: Mmap,
+    /// The position of the first byte after "Binary:\n"
+    data_offset: usize,
+    header: RawHeader,
+    variables: Vec<SimulationVariable>,
+    /// The position of every run of the sweep variable
+    step_ranges: Vec<StepRange>
+}
+
+/// A view on the values of one variable in a `MappedSimulation`.
+///
+/// The values are read from the mapped file, whenever they are accessed.
+#[derive(Debug,Clone,Copy)]
+pub struct Trace<'a> {
+    variable: &'a SimulationVariable,
+    header: &'a RawHeader,
+    var_index: usize,
+    /// The bytes of the file, starting with the first value of the trace
+    data: &'a [u8],
+    /// The distance between two values of the trace in bytes
+    stride: usize,
+    len: usize
+}
+
+/// An iterator over the values of a `Trace`. Every item is the real and the imaginary part.
+#[derive(Debug,Clone)]
+pub struct TraceIter<'a> {
+    trace: Trace<'a>,
+    pos: usize
+}
+
+impl MappedSimulation {
+    /// Maps the `.raw`-File into memory and reads its ASCII description.
+    ///
+    /// Only files with binary data can be mapped. The steps are found in the values of
+    /// the sweep variable, which is the only variable read during opening.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be changed or truncated by this or another process, as long as the
+    /// `MappedSimulation` exists. Otherwise the values of a `Trace` may change and reading a
+    /// truncated part of the file crashes the process (e.g. with SIGBUS). So only map files
+    /// of finished simulations, not files which are still written by LTspice or ngspice.
+    pub unsafe fn open<P: AsRef<Path>>(path_raw: P) -> Result<Self,ParseError> {
+        let file = fs::File::open(path_raw)?;
+        let map = Mmap::map(&file)?;
+        let (_,text,data_offset,format) = read_header(&mut &map[..])?;
+        if format != DataFormat::Binary {
+            return Err(ParseError::UnsupportedFormat(String::from("only binary data can be memory mapped")));
+        }
+        let (header,variables) = parse_description(&text,&map[data_offset..],format)?;
+        let expected = decode::point_size(&header) * header.nr_of_points;
+        if map.len() - data_offset < expected {
+            return Err(ParseError::TruncatedBinary {
+                expected: expected,
+                found: map.len() - data_offset
+            });
+        }
+
+        let mut simulation = MappedSimulation {
+            map: map,
+            data_offset: data_offset,
+            header: header,
+            variables: variables,
+            step_ranges: Vec::new()
+        };
+        simulation.step_ranges = if simulation.header.plot_kind.has_sweep_variable() {
+            let sweep: Vec<f64> = simulation.trace(&simulation.variables[0]).unwrap().iter().map(|(real,_)| real).collect();
+            find_step_ranges(&sweep)
+        } else {
+            (0..simulation.header.nr_of_points).map(|ct| StepRange { offset: ct, len: 1 }).collect()
+        };
+        Ok(simulation)
+    }
+
+    /// Returns the ASCII description of the `.raw`-File
+    pub fn header(&self) -> &RawHeader {
+        &self.header
+    }
+
+    /// Returns a vector of all available variables which resulted of the simulation
+    pub fn available_variables(&self) -> &Vec<SimulationVariable> {
+        &self.variables
+    }
+
+    /// Returns a variable, which has the given name. If no variable for that name can be found, it returns None
+    pub fn get_variable_for_name(&self, name: &str) -> Option<&SimulationVariable> {
+        self.variables.iter().find(|variable| variable.name == name)
+    }
+
+    /// Returns the position of every step in the data. A new step starts, whenever the sweep
+    /// variable returns to its start value, so every block of a nested `.dc` sweep is a step.
+    pub fn step_ranges(&self) -> &Vec<StepRange> {
+        &self.step_ranges
+    }
+
+    /// Returns the values of a variable for all steps together
+    pub fn trace<'a>(&'a self, var: &SimulationVariable) -> Option<Trace<'a>> {
+        self.trace_in_range(var,StepRange { offset: 0, len: self.header.nr_of_points })
+    }
+
+    /// Returns the values of a variable for the step with the given index.
+    /// Returns None, if the step or the variable is not part of this simulation.
+    pub fn trace_at<'a>(&'a self, step_index: usize, var: &SimulationVariable) -> Option<Trace<'a>> {
+        match self.step_ranges.get(step_index) {
+            Some(range) => self.trace_in_range(var,*range),
+            None => None
+        }
+    }
+
+    fn trace_in_range<'a>(&'a self, var: &SimulationVariable, range: StepRange) -> Option<Trace<'a>> {
+        let var_index = match self.variables.iter().position(|r| r.eq(var)) {
+            Some(var_index) => var_index,
+            None => return None
+        };
+        let size = decode::sample_size(&self.header,var_index);
+        let (start,stride) = if self.header.flags.fastaccess {
+            (decode::column_offset(&self.header,var_index),size)
+        } else {
+            let in_point: usize = (0..var_index).map(|index| decode::sample_size(&self.header,index)).sum();
+            (in_point,decode::point_size(&self.header))
+        };
+        let start = self.data_offset + start + range.offset * stride;
+        Some(Trace {
+            variable: &self.variables[var_index],
+            header: &self.header,
+            var_index: var_index,
+            // An empty trace (e.g. of a file without points) may start after the end of the file
+            data: self.map.get(start..).unwrap_or(&[]),
+            stride: stride,
+            len: range.len
+        })
+    }
+}
+
+impl<'a> Trace<'a> {
+    /// Returns the variable of this trace
+    pub fn variable(&self) -> &'a SimulationVariable {
+        self.variable
+    }
+
+    /// Returns the number of values
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true, if the trace has no values
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the real and the imaginary part of the value at the given index.
+    /// Real values have an imaginary part of zero.
+    ///
+    /// The time of a `.tran` plot is always positive (see `SteppedSimulation::compressed_points`).
+    pub fn get(&self, index: usize) -> Option<(f64,f64)> {
+        if index >= self.len {
+            return None;
+        }
+        let (real,imag) = decode::read_sample(&self.data[index * self.stride..],self.header,self.var_index);
+        if self.var_index == 0 && self.header.plot_kind == PlotKind::Transient {
+            Some((real.abs(),imag))
+        } else {
+            Some((real,imag))
+        }
+    }
+
+    /// Returns an iterator over all values
+    pub fn iter(&self) -> TraceIter<'a> {
+        TraceIter {
+            trace: *self,
+            pos: 0
+        }
+    }
+
+    /// Converts all values of the trace into a VariableResult
+    pub fn to_result(&self) -> VariableResult<'a> {
+        let (reals,imags) = self.iter().unzip();
+        VariableResult::new(self.variable,reals,imags)
+    }
+}
+
+impl<'a> Iterator for TraceIter<'a> {
+    type Item = (f64,f64);
+
+    fn next(&mut self) -> Option<(f64,f64)> {
+        let value = self.trace.get(self.pos);
+        if value.is_some() {
+            self.pos += 1;
+        }
+        value
+    }
+
+    fn size_hint(&self) -> (usize,Option<usize>) {
+        let remaining = self.trace.len - self.pos;
+        (remaining,Some(remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for TraceIter<'a> {}
@@ -0,0 +1,288 @@
+//! This module contains the results of the `.meas` statements, which LTspice writes into the `.log`-File
+//!
+//! Without `.step` every measurement is written in a single line:
+//!
+//! ```text
+//! vmax: MAX(v(out))=0.999 FROM 0 TO 0.001
+//! t1: v(out)=0.5 at 0.0005
+//! rise=1.1e-05 FROM 1e-06 TO 1.2e-05
+//! Measurement "fall" FAIL'ed
+//! ```
+//!
+//! With `.step` every measurement is written as a table with one row per step:
+//!
+//! ```text
+//! Measurement: vmax
+//!   step    MAX(v(out))    FROM    TO
+//!      1    0.999    0    0.001
+//!      2    0.666    0    0.001
+//! ```
+use units::parse_spice_number;
+
+
+/// All measurements of a simulation
+///
+/// # Examples
+/// ```no_run
+/// # use ltspice_parse::SteppedSimulation;
+/// # let results = SteppedSimulation::from_raw_file("Draft2.raw").unwrap();
+/// let measurements = results.measurements();
+/// let vmax = measurements.get("vmax").unwrap();
+/// for (step,value) in results.available_steps().iter().zip(vmax.values()) {
+///     println!("{:?}: {:?}", step, value.map(|value| value.value));
+/// }
+/// ```
+#[derive(Debug,Clone,Default,PartialEq)]
+pub struct Measurements {
+    measurements: Vec<Measurement>
+}
+
+/// The results of one `.meas` statement for every step
+#[derive(Debug,Clone,PartialEq)]
+pub struct Measurement {
+    /// The name of the measurement
+    pub name: String,
+    /// The measured expression, e.g. `MAX(v(out))`. This is empty, if LTspice does not write it.
+    pub expression: String,
+    values: Vec<Option<MeasuredValue>>
+}
+
+/// The result of a measurement in one step
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct MeasuredValue {
+    /// The measured value. For complex results (e.g. `(-3.01dB,-45°)`) this is the first part.
+    pub value: f64,
+    /// The start of the interval of the measurement (`FROM`)
+    pub from: Option<f64>,
+    /// The end of the interval of the measurement (`TO`)
+    pub to: Option<f64>,
+    /// The point of the measurement (`AT`)
+    pub at: Option<f64>
+}
+
+impl Measurements {
+    /// Parses all measurements of the text of a `.log`-File. Every measurement gets one value
+    /// per step, so the values are aligned to `SteppedSimulation::available_steps`.
+    pub fn parse(log_text: &str, nr_of_steps: usize) -> Measurements {
+        let nr_of_steps = nr_of_steps.max(1);
+        let mut measurements = Measurements::default();
+        let mut lines = log_text.lines().map(|line| line.trim_end_matches('\r')).peekable();
+        while let Some(line) = lines.next() {
+            if line.starts_with("Measurement:") {
+                let name = line["Measurement:".len()..].trim();
+                let columns = match lines.next() {
+                    Some(header) => split_columns(header),
+                    None => break
+                };
+                let mut measurement = Measurement::new(name,nr_of_steps);
+                if let Some(expression) = columns.get(1) {
+                    measurement.expression = (*expression).to_owned();
+                }
+                let position = |key: &str| columns.iter().skip(2).position(|col| col.eq_ignore_ascii_case(key)).map(|pos| pos + 2);
+                let (from,to,at) = (position("from"),position("to"),position("at"));
+                while let Some(row) = lines.peek().cloned() {
+                    let cols = split_columns(row);
+                    let step = match cols.first().and_then(|col| col.parse::<usize>().ok()) {
+                        Some(step) if step > 0 => step,
+                        _ => break
+                    };
+                    lines.next();
+                    if step > measurement.values.len() {
+                        measurement.values.resize(step,None);
+                    }
+                    let column = |index: Option<usize>| index.and_then(|index| cols.get(index)).and_then(|col| parse_value(col));
+                    measurement.values[step - 1] = column(Some(1)).map(|value| MeasuredValue {
+                        value: value,
+                        from: column(from),
+                        to: column(to),
+                        at: column(at)
+                    });
+                }
+                measurements.measurements.push(measurement);
+            } else if line.starts_with("Measurement \"") {
+                // A failed measurement without `.step`
+                let name = line["Measurement \"".len()..].split('"').next().unwrap_or("");
+                measurements.measurements.push(Measurement::new(name,nr_of_steps));
+            } else if let Some(measurement) = parse_line(line,nr_of_steps) {
+                measurements.measurements.push(measurement);
+            }
+        }
+        measurements
+    }
+
+    /// Returns the names of all measurements in the order of the `.log`-File
+    pub fn names(&self) -> Vec<&str> {
+        self.measurements.iter().map(|measurement| measurement.name.as_str()).collect()
+    }
+
+    /// Returns the measurement with the given name. The names are not case sensitive, like in SPICE.
+    pub fn get(&self, name: &str) -> Option<&Measurement> {
+        self.measurements.iter().find(|measurement| measurement.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns all measurements
+    pub fn all(&self) -> &Vec<Measurement> {
+        &self.measurements
+    }
+
+    /// Returns the number of measurements
+    pub fn len(&self) -> usize {
+        self.measurements.len()
+    }
+
+    /// Returns true, if the `.log`-File contains no measurements
+    pub fn is_empty(&self) -> bool {
+        self.measurements.is_empty()
+    }
+
+    /// Aligns the values of every measurement to the given number of steps. Missing steps
+    /// (e.g. of a simulation without `.step`, whose steps are found in the data) have no value.
+    pub(crate) fn align_to_steps(&mut self, nr_of_steps: usize) {
+        for measurement in &mut self.measurements {
+            measurement.values.resize(nr_of_steps,None);
+        }
+    }
+}
+
+impl Measurement {
+    /// Creates a measurement, which failed in every step
+    fn new(name: &str, nr_of_steps: usize) -> Measurement {
+        Measurement {
+            name: name.to_owned(),
+            expression: String::new(),
+            values: vec![None;nr_of_steps]
+        }
+    }
+
+    /// Returns the results of every step. Failed measurements are None.
+    pub fn values(&self) -> &Vec<Option<MeasuredValue>> {
+        &self.values
+    }
+
+    /// Returns the measured value of the step with the given index (see
+    /// `SteppedSimulation::available_steps`). Returns None, if the measurement failed.
+    pub fn value_at(&self, step_index: usize) -> Option<f64> {
+        match self.values.get(step_index) {
+            Some(&Some(value)) => Some(value.value),
+            _ => None
+        }
+    }
+}
+
+/// Parses a line like `vmax: MAX(v(out))=0.999 FROM 0 TO 0.001` or `rise=1.1e-05 FROM 1e-06 TO 1.2e-05`.
+///
+/// A line without `:` is only a measurement, if it has a `FROM`, `TO` or `AT` annotation, so
+/// other assignments in the `.log`-File (e.g. `x=1`) are not mistaken for measurements.
+/// Returns None for all other lines of the `.log`-File.
+fn parse_line(line: &str, nr_of_steps: usize) -> Option<Measurement> {
+    let mut tokens = line.split_whitespace().peekable();
+    let first = match tokens.next() {
+        Some(first) => first,
+        None => return None
+    };
+    let (name,assignment) = if first.ends_with(':') {
+        (&first[..first.len()-1],tokens.next().unwrap_or(""))
+    } else {
+        let annotated = match tokens.peek() {
+            Some(key) => ["from","to","at"].iter().any(|annotation| key.eq_ignore_ascii_case(annotation)),
+            None => false
+        };
+        if !annotated {
+            return None;
+        }
+        ("",first)
+    };
+    let (expression,value) = match assignment.rfind('=') {
+        Some(pos) => (&assignment[..pos],&assignment[pos+1..]),
+        None => return None
+    };
+    let name = if name.is_empty() { expression } else { name };
+    let is_name = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+    let value = match parse_value(value) {
+        Some(value) if is_name => value,
+        _ => return None
+    };
+
+    let mut measured = MeasuredValue {
+        value: value,
+        from: None,
+        to: None,
+        at: None
+    };
+    while let Some(key) = tokens.next() {
+        let value = tokens.next().and_then(parse_spice_number);
+        match key.to_lowercase().as_str() {
+            "from" => measured.from = value,
+            "to" => measured.to = value,
+            "at" => measured.at = value,
+            _ => {}
+        }
+    }
+    let mut measurement = Measurement::new(name,nr_of_steps);
+    if expression != name {
+        measurement.expression = expression.to_owned();
+    }
+    // Without `.step` the value belongs to every step (there is only one)
+    for step in measurement.values.iter_mut() {
+        *step = Some(measured);
+    }
+    Some(measurement)
+}
+
+/// Splits a line of a table by tabs (or by whitespace, if there are no tabs)
+fn split_columns(line: &str) -> Vec<&str> {
+    if line.contains('\t') {
+        line.split('\t').map(|col| col.trim()).filter(|col| !col.is_empty()).collect()
+    } else {
+        line.split_whitespace().collect()
+    }
+}
+
+/// Parses a value like `0.999`, `1.1e-05` or `(-3.01dB,-45°)`
+fn parse_value(text: &str) -> Option<f64> {
+    let text = text.trim_start_matches('(');
+    parse_spice_number(text.split(',').next().unwrap_or(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Measurements;
+
+    #[test]
+    fn single_lines() {
+        let log = "tnom = 27\nx=1\nvmax: MAX(v(out))=0.999 FROM 0 TO 0.001\nt1: v(out)=0.5 at 0.0005\n\
+                   rise=1.1e-05 FROM 1e-06 TO 1.2e-05\nMeasurement \"fall\" FAIL'ed\n";
+        let measurements = Measurements::parse(log,0);
+        assert_eq!(measurements.names(), vec!["vmax","t1","rise","fall"]);
+        let vmax = measurements.get("VMAX").unwrap();
+        assert_eq!(vmax.expression, "MAX(v(out))");
+        let value = vmax.values()[0].unwrap();
+        assert_eq!((value.value,value.from,value.to,value.at), (0.999,Some(0.0),Some(0.001),None));
+        assert_eq!(measurements.get("t1").unwrap().values()[0].unwrap().at, Some(0.0005));
+        assert_eq!(measurements.get("rise").unwrap().value_at(0), Some(1.1e-05));
+        assert_eq!(measurements.get("fall").unwrap().value_at(0), None);
+    }
+
+    #[test]
+    fn stepped_tables() {
+        let log = ".step r=1\n.step r=2\n.step r=3\n\nMeasurement: vmax\n  step\tMAX(v(out))\tFROM\tTO\n     1\t0.999\t0\t0.001\n     3\t0.5\t0\t0.001\n\n\
+                   Measurement: gain\n  step\tv(out)\tAT\n     1\t(-3.01dB,-45\u{b0})\t1000\n\n";
+        let measurements = Measurements::parse(log,3);
+        let vmax = measurements.get("vmax").unwrap();
+        assert_eq!(vmax.values().len(), 3);
+        assert_eq!(vmax.value_at(0), Some(0.999));
+        assert_eq!(vmax.value_at(1), None);
+        assert_eq!(vmax.value_at(2), Some(0.5));
+        let gain = measurements.get("gain").unwrap().values()[0].unwrap();
+        assert_eq!((gain.value,gain.at), (-3.01,Some(1000.0)));
+    }
+
+    #[test]
+    fn aligned_to_steps() {
+        let mut measurements = Measurements::parse("Measurement: vmax\n  step\tMAX(v(out))\n     1\t1\n     2\t2\n     3\t3\n",3);
+        measurements.align_to_steps(2);
+        assert_eq!(measurements.get("vmax").unwrap().values().len(), 2);
+        measurements.align_to_steps(4);
+        assert_eq!(measurements.get("vmax").unwrap().value_at(3), None);
+    }
+}
@@ -1,9 +1,11 @@
 //! This module contains all Functions to work with a single Step
 //!
 //! The most important struct is the VariableResult. This struct contains all data and various functions to work with that data.
+use std::sync::OnceLock;
 use num::complex::Complex;
 use gnuplot::{Figure, Caption, Color,AxesCommon,AutoOption};
-use std::str::FromStr;
+use error::ParseError;
+use units::parse_spice_number;
 
 
 /// This struct contains all data for one result of a simulation step. The data is accessible in different formats
@@ -11,6 +13,11 @@ use std::str::FromStr;
 /// # Examples
 ///
 /// ```no_run
+/// # use ltspice_parse::SteppedSimulation;
+/// # use ltspice_parse::results::DataType;
+/// # let results = SteppedSimulation::from_raw_file("Draft2.raw").unwrap();
+/// # let vout = results.get_variable_for_name("V(vout)").unwrap();
+/// # let result = results.get_values_for_variable_at(&results.available_steps()[0],vout).unwrap();
 /// //Gets the avsolute values in decibel
 /// let decibel_data = result.get_data(&DataType::AbsoluteDecibel);
 /// let starting_freq = decibel_data[0];
@@ -19,7 +26,9 @@ use std::str::FromStr;
 pub struct VariableResult<'a> {
     variable: &'a SimulationVariable,
     reals: Vec<f64>,
-    imags: Vec<f64>
+    imags: Vec<f64>,
+    /// The unwrapped argument is calculated once, because every point depends on all previous points
+    unwrapped_arg: OnceLock<Vec<f64>>
 }
 
 
@@ -31,7 +40,8 @@ impl<'a> VariableResult<'a> {
 		VariableResult {
 			variable: variable,
 			reals: reals,
-			imags: imags
+			imags: imags,
+			unwrapped_arg: OnceLock::new()
 		}
 	}
 	
@@ -40,6 +50,12 @@ impl<'a> VariableResult<'a> {
 	/// The contents are determined by the `data_type`
 	/// ## Example
 	/// ```no_run
+	/// # use ltspice_parse::SteppedSimulation;
+	/// # use ltspice_parse::results::*;
+	/// # let results = SteppedSimulation::from_raw_file("Draft2.raw").unwrap();
+	/// # let step = &results.available_steps()[0];
+	/// # let vout = results.get_variable_for_name("V(vout)").unwrap();
+	/// # let result = results.get_values_for_variable_at(step,vout).unwrap();
 	/// let imaginary_values = result.get_data(&DataType::Imaginary);
 	/// ```
     pub fn get_data(&self,data_type:&DataType) -> Vec<f64>{
@@ -48,7 +64,10 @@ impl<'a> VariableResult<'a> {
             &DataType::Imaginary         => self.imags.clone(),
             &DataType::Absolute          => self.get_abs(),
             &DataType::AbsoluteDecibel   => self.get_abs_in_decibel(),
-            &DataType::Argument          => unimplemented!()
+            &DataType::Argument          => self.get_arg(),
+            &DataType::ArgumentDegrees   => self.get_arg().iter().map(|arg| arg.to_degrees()).collect(),
+            &DataType::UnwrappedArgument => self.get_unwrapped_arg().clone(),
+            &DataType::UnwrappedArgumentDegrees => self.get_unwrapped_arg().iter().map(|arg| arg.to_degrees()).collect()
         }
     }
     
@@ -62,6 +81,31 @@ impl<'a> VariableResult<'a> {
         result
     }
 
+    fn get_arg(&self) -> Vec<f64> {
+        let mut result:Vec<f64> = Vec::new();
+        for ct in 0..self.reals.len() {
+            result.push(Complex::new(self.reals[ct],self.imags[ct]).arg());
+        }
+        result
+    }
+
+    /// Removes the jumps of 2*pi between neighbouring points of the argument
+    fn get_unwrapped_arg(&self) -> &Vec<f64> {
+        self.unwrapped_arg.get_or_init(|| {
+            let two_pi = 2.0 * ::std::f64::consts::PI;
+            let wrapped = self.get_arg();
+            let mut result:Vec<f64> = Vec::new();
+            let mut offset = 0.0;
+            for ct in 0..wrapped.len() {
+                if ct > 0 {
+                    offset -= two_pi * ((wrapped[ct] - wrapped[ct-1]) / two_pi).round();
+                }
+                result.push(wrapped[ct] + offset);
+            }
+            result
+        })
+    }
+
     fn get_abs_in_decibel(&self) -> Vec<f64> {
         let mut result:Vec<f64> = Vec::new();
         for ct in 0..self.reals.len() {
@@ -88,6 +132,12 @@ impl<'a> VariableResult<'a> {
     ///
     /// ## Example 
     /// ```no_run
+    /// # use ltspice_parse::SteppedSimulation;
+    /// # use ltspice_parse::results::*;
+    /// # let results = SteppedSimulation::from_raw_file("Draft2.raw").unwrap();
+    /// # let step = &results.available_steps()[0];
+    /// # let vout = results.get_variable_for_name("V(vout)").unwrap();
+    /// # let result = results.get_values_for_variable_at(step,vout).unwrap();
     ///	let peaks = result.find_peaks(Some(PeakType::Maximum),&DataType::AbsoluteDecibel);
     /// ```
     pub fn find_peaks(&self,peak_type: Option<PeakType>,data_type:&DataType) -> Vec<usize> {
@@ -129,7 +179,10 @@ impl<'a> VariableResult<'a> {
             &DataType::Imaginary         => Some(self.imags[index]),
             &DataType::Absolute          => Some(Complex::new(self.reals[index],self.imags[index]).norm()),
             &DataType::AbsoluteDecibel   => Some(20.0*Complex::new(self.reals[index],self.imags[index]).norm().log(10.0)),
-            &DataType::Argument          => unimplemented!()
+            &DataType::Argument          => Some(Complex::new(self.reals[index],self.imags[index]).arg()),
+            &DataType::ArgumentDegrees   => Some(Complex::new(self.reals[index],self.imags[index]).arg().to_degrees()),
+            &DataType::UnwrappedArgument => Some(self.get_unwrapped_arg()[index]),
+            &DataType::UnwrappedArgumentDegrees => Some(self.get_unwrapped_arg()[index].to_degrees())
         }
     	} else {
     		None
@@ -171,8 +224,8 @@ impl<'a> VariableResult<'a> {
         let original = self.get_data(data_type);
         let normalized = VariableResult::diff(&frequency.normalize(&DataType::Real));
         let mut result = 0.0;
-        for ct in 0..original.len()-1 {
-            result += (original[ct+1]+original[ct])/2.0*normalized[ct];
+        for ct in 1..original.len().min(normalized.len() + 1) {
+            result += (original[ct]+original[ct-1])/2.0*normalized[ct-1];
         }
         result
     }
@@ -180,26 +233,24 @@ impl<'a> VariableResult<'a> {
     /// Searches the dataset for the first value after the given search frequency. To search for a value near 500 Hz call
     ///
     /// ```no_run
-    /// let (position_in_dataset, value) = find_value_near_freq(&DataType::AbsoluteDecibel,&frequency_dataset,500.0)
+    /// # use ltspice_parse::SteppedSimulation;
+    /// # use ltspice_parse::results::*;
+    /// # let results = SteppedSimulation::from_raw_file("Draft2.raw").unwrap();
+    /// # let step = &results.available_steps()[0];
+    /// # let vout = results.get_variable_for_name("V(vout)").unwrap();
+    /// # let result = results.get_values_for_variable_at(step,vout).unwrap();
+    /// # let frequency_dataset = results.get_values_for_variable_at(step,&results.available_variables()[0]).unwrap();
+    /// let (position_in_dataset, value) = result.find_value_near_freq(&DataType::AbsoluteDecibel,&frequency_dataset,500.0).unwrap();
     /// ```
-    pub fn find_value_near_freq(&self, data_type:&DataType, frequency: &VariableResult, search_freq:f64) -> (usize,f64) {
+    /// Returns None, if all frequencies are lower than the search frequency.
+    pub fn find_value_near_freq(&self, data_type:&DataType, frequency: &VariableResult, search_freq:f64) -> Option<(usize,f64)> {
      	let fqs = frequency.get_data(&DataType::Real);
-     	let mut index = 0;
-     	let mut found = false;
      	for ct in 0..frequency.len(){
      		if fqs[ct] >= search_freq {
-     			index = ct;
-     			found = true;
-     			break;
+     			return self.get_data_point(data_type,ct).map(|value| (ct,value));
      		}
      	}
-     	if !found {
-     		println!("Frequenzen sind: {:#?} Gesucht ist {:?}", fqs,search_freq);
-     		panic!("Frequenz nicht vorhanden!");
-     	}
-     	
-     	(index,self.get_data_point(data_type,index).unwrap())
-     	
+     	None
      }
     
     /// Returns the next occurence of a value higher/lower than the value at the starting
@@ -249,6 +300,56 @@ impl<'a> VariableResult<'a> {
         (if found_left {Some (position_left)} else  {None}, if found_right {Some (position_right)} else  {None})
     }
     
+	/// Calculates the group delay `-d(phase)/d(omega)` in seconds from the unwrapped argument.
+	///
+	/// Like `diff`, the result has one value less than the data set: Every value belongs to
+	/// the interval between two neighbouring frequencies.
+	pub fn group_delay(&self,frequency: &VariableResult) -> Vec<f64> {
+		if self.len() < 2 {
+			return Vec::new();
+		}
+		let phase = VariableResult::diff(&self.get_data(&DataType::UnwrappedArgument));
+		let freqs = VariableResult::diff(&frequency.get_data(&DataType::Real));
+		let mut result = Vec::new();
+		for ct in 0..phase.len() {
+			result.push(-phase[ct]/(2.0*::std::f64::consts::PI*freqs[ct]));
+		}
+		result
+	}
+
+	/// Integrates a noise spectral density (e.g. `V(onoise)` in V/Hz½) from `start` to `stop` Hz
+	/// and returns the total RMS noise, like the waveform viewer of LTspice does.
+	///
+	/// The squared density is integrated with the trapezoidal rule. Points at the borders of the
+	/// band are interpolated linearly. Returns 0.0, if the band is outside of the simulation.
+	///
+	/// ## Example
+	/// ```no_run
+	/// # use ltspice_parse::SteppedSimulation;
+	/// # use ltspice_parse::results::*;
+	/// # let results = SteppedSimulation::from_raw_file("Draft2.raw").unwrap();
+	/// # let step = &results.available_steps()[0];
+	/// # let freq = results.get_values_for_variable_at(step,&results.available_variables()[0]).unwrap();
+	/// # let onoise = results.get_values_for_variable_at(step,results.get_variable_for_name("V(onoise)").unwrap()).unwrap();
+	/// let rms = onoise.integrate_noise(&freq, 10.0, 20e3);
+	/// ```
+	pub fn integrate_noise(&self,frequency: &VariableResult,start: f64,stop: f64) -> f64 {
+		let freqs = frequency.get_data(&DataType::Real);
+		let power: Vec<f64> = self.reals.iter().zip(self.imags.iter()).map(|(re,im)| re*re+im*im).collect();
+		let mut sum = 0.0;
+		for ct in 1..freqs.len().min(power.len()) {
+			let (f1,f2) = (freqs[ct-1],freqs[ct]);
+			let low = f1.max(start);
+			let high = f2.min(stop);
+			if high <= low {
+				continue;
+			}
+			let interpolate = |f: f64| power[ct-1] + (power[ct]-power[ct-1])*(f-f1)/(f2-f1);
+			sum += (interpolate(low)+interpolate(high))/2.0*(high-low);
+		}
+		sum.sqrt()
+	}
+
 	/// Used for myself. Not really documented
 	pub fn calculate_resonance_penalty(&self,frequency: &VariableResult) -> f64{
 		let resonances = self.find_peaks(Some(PeakType::Maximum), &DataType::AbsoluteDecibel);
@@ -298,12 +399,12 @@ impl<'a> VariableResult<'a> {
 
 
 	/// Used for myself. Not really documented
-    pub fn calculate_fitness(&self, frequency: &VariableResult, averages: [f64;5], deviations: [f64;5]) -> ([f64;6],f64,[f64;6]) {
+    pub fn calculate_fitness(&self, frequency: &VariableResult, averages: [f64;5], deviations: [f64;5]) -> Result<([f64;6],f64,[f64;6]),ParseError> {
     	
         let avg = self.avg_normalized(&DataType::AbsoluteDecibel,&frequency);
         //println!("{:?}", avg);
-        let (_,one_k_value) = self.find_value_near_freq(&DataType::AbsoluteDecibel,&frequency, 1000.0);
-        let (_,one_h_value) = self.find_value_near_freq(&DataType::AbsoluteDecibel,&frequency, 100.0);
+        let (_,one_k_value) = self.find_value_near_freq(&DataType::AbsoluteDecibel,&frequency, 1000.0).ok_or(ParseError::FrequencyNotFound(1000.0))?;
+        let (_,one_h_value) = self.find_value_near_freq(&DataType::AbsoluteDecibel,&frequency, 100.0).ok_or(ParseError::FrequencyNotFound(100.0))?;
         let (_,max) = self.max(&DataType::AbsoluteDecibel);
         let (_,min) = self.min(&DataType::AbsoluteDecibel);
       
@@ -332,7 +433,7 @@ impl<'a> VariableResult<'a> {
         
         
         
-        (arguments, arguments.into_iter().fold(0.0, |acc, &x| acc + x),original_values_of_arguments)
+        Ok((arguments, arguments.into_iter().fold(0.0, |acc, &x| acc + x),original_values_of_arguments))
     }
     
    
@@ -360,8 +461,15 @@ pub enum DataType {
     Absolute,
     /// Take the absolute part of the complex value as decibel (follows: `20*log(abs(x))`)
     AbsoluteDecibel,
-    /// Take the argument of the complex value. This is not yet implemented.
-    Argument
+    /// Take the argument of the complex value in radians (between -pi and pi)
+    Argument,
+    /// Take the argument of the complex value in degrees (between -180 and 180)
+    ArgumentDegrees,
+    /// Take the argument of the complex value in radians. Jumps of 2*pi between
+    /// neighbouring points are removed, so the phase is continuous over the sweep.
+    UnwrappedArgument,
+    /// Take the unwrapped argument of the complex value in degrees (jumps of 360° are removed)
+    UnwrappedArgumentDegrees
 }
 
 
@@ -371,21 +479,30 @@ pub enum PeakType {
 }
 
 /// This struct contains the name and the value of one param in a step
-#[derive(Default,Debug,PartialEq)]
+#[derive(Default,Debug,Clone,PartialEq)]
 pub struct SteppingVariable {
 	/// The name of the `.param` parameter
     pub name: String,
     /// The value of the parameter in this step
-    pub value: f32
+    pub value: f64
 }
 
 impl SteppingVariable {
 	/// Creates a new SteppingVariable using the string format, which can be usually found in the `.log` file
-    pub fn new(log_excerpt:&str) -> Self {
+	///
+	/// The value may have an engineering suffix like `1n` or `10meg` (see `parse_spice_number`).
+	/// Returns an error, if the excerpt does not look like `name=value`.
+    pub fn new(log_excerpt:&str) -> Result<Self,ParseError> {
         let split:Vec<&str> = log_excerpt.split('=').collect();
-        SteppingVariable {
-            name: split[0].to_owned(),
-            value: f32::from_str(split[1]).unwrap()
+        if split.len() != 2 {
+            return Err(ParseError::MalformedHeader(log_excerpt.to_owned()));
+        }
+        match parse_spice_number(split[1]) {
+            Some(value) => Ok(SteppingVariable {
+                name: split[0].to_owned(),
+                value: value
+            }),
+            None => Err(ParseError::MalformedHeader(log_excerpt.to_owned()))
         }
     }
 }
@@ -399,23 +516,256 @@ pub struct SimulationVariable {
     pub name:String,
     /// The type of the variable
     pub var_type:VariableType,
+    /// The type as it is written in the `Variables:` section, e.g. `subckt_current`
+    pub type_name:String,
 }
 
 /// This enum is used to determine the physical type of the variable
-#[derive(Debug,Hash,Eq,PartialEq)]
+#[derive(Debug,Clone,Copy,Hash,Eq,PartialEq)]
 pub enum VariableType {
+    /// `time`
+    Time,
+    /// `frequency`
     Frequency,
+    /// `voltage`
     Voltage,
+    /// `device_current`, `subckt_current` and `current`
     Current,
+    /// `voltage_density` - a noise spectral density in V/Hz½
+    VoltageDensity,
+    /// `current_density` - a noise spectral density in A/Hz½
+    CurrentDensity,
+    /// `power`
+    Power,
+    /// The gain from the input to the output of a `.noise` simulation
+    Gain,
+    /// `temperature` (LTspice) and `temp-sweep` (ngspice)
+    Temperature,
+    /// `res-sweep` (ngspice)
+    Resistance,
+    /// `param` - a swept parameter of a `.dc` or `.step` sweep
+    Param,
+    /// `notype` (ngspice)
+    NoType,
+    /// `impedance` (ngspice)
+    Impedance,
+    /// `admittance` (ngspice)
+    Admittance,
+    /// `phase` (ngspice)
+    Phase,
+    /// `decibel` (ngspice)
+    Decibel,
+    /// `capacitance` (ngspice)
+    Capacitance,
+    /// `charge` (ngspice)
+    Charge,
+    /// `sqr-voltage-density` (ngspice) - a noise spectral density in V²/Hz
+    SqrVoltageDensity,
+    /// `sqr-current-density` (ngspice) - a noise spectral density in A²/Hz
+    SqrCurrentDensity,
+    /// `sqr-voltage` (ngspice)
+    SqrVoltage,
+    /// `sqr-current` (ngspice)
+    SqrCurrent,
+    /// `pole` (ngspice) - a pole of a `.pz` analysis
+    Pole,
+    /// `zero` (ngspice) - a zero of a `.pz` analysis
+    Zero,
+    /// `s-param` (ngspice)
+    SParam,
     Unknown
 }
 
+impl VariableType {
+    /// Determines the type from the third column of the `Variables:` section.
+    /// Types, which are not known, become `Unknown`.
+    pub fn from_type_name(type_name: &str) -> VariableType {
+        match type_name.trim().to_lowercase().as_str() {
+            "time" => VariableType::Time,
+            "frequency" => VariableType::Frequency,
+            "voltage" => VariableType::Voltage,
+            "device_current" | "subckt_current" | "current" => VariableType::Current,
+            "voltage_density" | "voltage-density" => VariableType::VoltageDensity,
+            "current_density" | "current-density" => VariableType::CurrentDensity,
+            "power" => VariableType::Power,
+            "gain" => VariableType::Gain,
+            "temperature" | "temp-sweep" => VariableType::Temperature,
+            "res-sweep" => VariableType::Resistance,
+            "param" => VariableType::Param,
+            "notype" => VariableType::NoType,
+            "impedance" => VariableType::Impedance,
+            "admittance" => VariableType::Admittance,
+            "phase" => VariableType::Phase,
+            "decibel" => VariableType::Decibel,
+            "capacitance" => VariableType::Capacitance,
+            "charge" => VariableType::Charge,
+            "sqr-voltage-density" => VariableType::SqrVoltageDensity,
+            "sqr-current-density" => VariableType::SqrCurrentDensity,
+            "sqr-voltage" => VariableType::SqrVoltage,
+            "sqr-current" => VariableType::SqrCurrent,
+            "pole" => VariableType::Pole,
+            "zero" => VariableType::Zero,
+            "s-param" => VariableType::SParam,
+            _ => VariableType::Unknown
+        }
+    }
+
+    /// Returns the name of the type, which LTspice writes into the `Variables:` section.
+    /// Unknown types are written as `notype`.
+    ///
+    /// Several names map to the same type (e.g. `device_current` and `subckt_current`), so
+    /// this is not always the name in the file. See `SimulationVariable::type_name` for that.
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            VariableType::Time => "time",
+            VariableType::Frequency => "frequency",
+            VariableType::Voltage => "voltage",
+            VariableType::Current => "device_current",
+            VariableType::VoltageDensity => "voltage_density",
+            VariableType::CurrentDensity => "current_density",
+            VariableType::Power => "power",
+            VariableType::Gain => "gain",
+            VariableType::Temperature => "temperature",
+            VariableType::Resistance => "res-sweep",
+            VariableType::Param => "param",
+            VariableType::Impedance => "impedance",
+            VariableType::Admittance => "admittance",
+            VariableType::Phase => "phase",
+            VariableType::Decibel => "decibel",
+            VariableType::Capacitance => "capacitance",
+            VariableType::Charge => "charge",
+            VariableType::SqrVoltageDensity => "sqr-voltage-density",
+            VariableType::SqrCurrentDensity => "sqr-current-density",
+            VariableType::SqrVoltage => "sqr-voltage",
+            VariableType::SqrCurrent => "sqr-current",
+            VariableType::Pole => "pole",
+            VariableType::Zero => "zero",
+            VariableType::SParam => "s-param",
+            VariableType::NoType | VariableType::Unknown => "notype"
+        }
+    }
+
+    /// Returns the physical unit of the type, e.g. `V` or `Hz`. Types without a unit
+    /// (gain, parameters, poles, zeros, s-parameters and unknown types) return an empty string.
+    pub fn unit(&self) -> &'static str {
+        match *self {
+            VariableType::Time => "s",
+            VariableType::Frequency => "Hz",
+            VariableType::Voltage => "V",
+            VariableType::Current => "A",
+            VariableType::VoltageDensity => "V/\u{221a}Hz",
+            VariableType::CurrentDensity => "A/\u{221a}Hz",
+            VariableType::Power => "W",
+            VariableType::Temperature => "\u{b0}C",
+            VariableType::Resistance | VariableType::Impedance => "\u{3a9}",
+            VariableType::Admittance => "S",
+            VariableType::Phase => "\u{b0}",
+            VariableType::Decibel => "dB",
+            VariableType::Capacitance => "F",
+            VariableType::Charge => "C",
+            VariableType::SqrVoltageDensity => "V\u{b2}/Hz",
+            VariableType::SqrCurrentDensity => "A\u{b2}/Hz",
+            VariableType::SqrVoltage => "V\u{b2}",
+            VariableType::SqrCurrent => "A\u{b2}",
+            VariableType::Gain | VariableType::Param | VariableType::Pole | VariableType::Zero |
+            VariableType::SParam | VariableType::NoType | VariableType::Unknown => ""
+        }
+    }
+}
+
 /// A step is a list of SteppingVariables
 pub type Step = Vec<SteppingVariable>;
 
+/// The position of one step in the data of a simulation
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct StepRange {
+	/// The index of the first point of the step
+	pub offset: usize,
+	/// The number of points of the step
+	pub len: usize
+}
+
+/// The number of points of a truncated `.raw`-File
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct Truncation {
+	/// The number of points given by `No. Points`
+	pub expected: usize,
+	/// The number of complete points in the file
+	pub found: usize
+}
+
 /// Calculates the logistic function which is scaled by a scaling factor and an offset
 fn logistic_function(input:f64,scale:f64,offset:f64) -> f64 {
 	let x = (input-offset)/scale ;
 	let e:f64 = 2.71828182846;
 	(1.0/(1.0+e.powf(-x)))
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::{DataType, SimulationVariable, VariableResult, VariableType};
+    use std::f64::consts::PI;
+
+    fn variable(name: &str, var_type: VariableType) -> SimulationVariable {
+        SimulationVariable {
+            id: 0,
+            name: name.to_owned(),
+            var_type: var_type,
+            type_name: var_type.type_name().to_owned()
+        }
+    }
+
+    /// Returns complex values with an absolute value of 1 and the given arguments
+    fn from_args<'a>(var: &'a SimulationVariable, args: &[f64]) -> VariableResult<'a> {
+        VariableResult::new(var, args.iter().map(|arg| arg.cos()).collect(), args.iter().map(|arg| arg.sin()).collect())
+    }
+
+    fn assert_all_close(values: &[f64], expected: &[f64]) {
+        assert_eq!(values.len(), expected.len());
+        for (value, expected) in values.iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-9, "{:?} != {:?}", values, expected);
+        }
+    }
+
+    #[test]
+    fn unwrapped_argument_passes_pi() {
+        let var = variable("V(out)", VariableType::Voltage);
+        let args = [0.0, 2.0, 4.0, 6.0, -1.0, -3.0, -5.0];
+        let result = from_args(&var, &args);
+        let wrapped = result.get_data(&DataType::Argument);
+        assert!(wrapped.iter().all(|arg| arg.abs() <= PI));
+        // The jump from 6 to -1 is smaller than pi, so it is kept
+        let unwrapped = [0.0, 2.0, 4.0, 6.0, 2.0 * PI - 1.0, 2.0 * PI - 3.0, 2.0 * PI - 5.0];
+        assert_all_close(&result.get_data(&DataType::UnwrappedArgument), &unwrapped);
+        let points: Vec<f64> = (0..args.len()).map(|ct| result.get_data_point(&DataType::UnwrappedArgument, ct).unwrap()).collect();
+        assert_all_close(&points, &unwrapped);
+        let degrees: Vec<f64> = unwrapped.iter().map(|arg| arg.to_degrees()).collect();
+        assert_all_close(&result.get_data(&DataType::UnwrappedArgumentDegrees), &degrees);
+        assert_eq!(result.get_data_point(&DataType::UnwrappedArgument, args.len()), None);
+    }
+
+    #[test]
+    fn group_delay_of_a_delay_line() {
+        // A delay of 1 ms turns the phase by -2*pi*f*1ms, which wraps several times
+        let freq_var = variable("frequency", VariableType::Frequency);
+        let var = variable("V(out)", VariableType::Voltage);
+        let freqs: Vec<f64> = (0..20).map(|ct| ct as f64 * 100.0).collect();
+        let args: Vec<f64> = freqs.iter().map(|f| -2.0 * PI * f * 1e-3).collect();
+        let frequency = VariableResult::new(&freq_var, freqs.clone(), vec![0.0; freqs.len()]);
+        let result = from_args(&var, &args);
+        assert_all_close(&result.group_delay(&frequency), &[1e-3; 19]);
+        assert!(from_args(&var, &[0.0]).group_delay(&frequency).is_empty());
+    }
+    #[test]
+    fn noise_of_a_flat_density() {
+        // 10 nV/Hz½ from 1 Hz to 100 kHz. The band borders are between the points.
+        let freq_var = variable("frequency", VariableType::Frequency);
+        let var = variable("V(onoise)", VariableType::VoltageDensity);
+        let freqs = vec![1.0, 3.0, 30.0, 300.0, 3e3, 30e3, 100e3];
+        let frequency = VariableResult::new(&freq_var, freqs.clone(), vec![0.0; freqs.len()]);
+        let onoise = VariableResult::new(&var, vec![10e-9; freqs.len()], vec![0.0; freqs.len()]);
+        let rms = onoise.integrate_noise(&frequency, 10.0, 20e3);
+        let expected = 10e-9 * (20e3f64 - 10.0).sqrt();
+        assert!((rms - expected).abs() < expected * 1e-12, "{} != {}", rms, expected);
+        assert_eq!(onoise.integrate_noise(&frequency, 200e3, 300e3), 0.0);
+    }
+}
@@ -0,0 +1,143 @@
+//! This module contains functions to work with numbers and units as SPICE writes them
+//!
+//! SPICE numbers can have an engineering suffix (e.g. `4.7k` or `10meg`), which may be
+//! followed by an arbitrary unit (e.g. `1nF`). The suffixes are not case sensitive.
+
+/// The engineering suffixes and their factors. Longer suffixes have to come first,
+/// so `meg` and `mil` are not mistaken for `m`.
+const SUFFIXES: [(&'static str, f64);12] = [
+    ("meg", 1e6),
+    ("mil", 25.4e-6),
+    ("f", 1e-15),
+    ("p", 1e-12),
+    ("n", 1e-9),
+    ("u", 1e-6),
+    ("\u{b5}", 1e-6),
+    ("\u{3bc}", 1e-6),
+    ("m", 1e-3),
+    ("k", 1e3),
+    ("g", 1e9),
+    ("t", 1e12)
+];
+
+/// Parses a number like `1n`, `4.7µ`, `10meg`, `2.2k`, `1e-3` or `100pF`.
+///
+/// Returns None, if the text does not start with a number.
+///
+/// # Examples
+/// ```
+/// # use ltspice_parse::units::parse_spice_number;
+/// assert_eq!(parse_spice_number("2.2k"), Some(2200.0));
+/// assert_eq!(parse_spice_number("10megHz"), Some(10e6));
+/// ```
+pub fn parse_spice_number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let number_len = number_prefix_len(text);
+    let number = match text[..number_len].parse::<f64>() {
+        Ok(number) => number,
+        Err(_) => return None
+    };
+    let suffix = text[number_len..].to_lowercase();
+    for &(name, factor) in SUFFIXES.iter() {
+        if suffix.starts_with(name) {
+            return Some(number * factor);
+        }
+    }
+    // Everything else is just a unit like `V` or `Hz`
+    Some(number)
+}
+
+/// Returns the length of the leading floating point number (sign, digits, point and exponent)
+fn number_prefix_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let mut pos = 0;
+    if pos < bytes.len() && (bytes[pos] == b'+' || bytes[pos] == b'-') {
+        pos += 1;
+    }
+    while pos < bytes.len() && (bytes[pos].is_ascii_digit() || bytes[pos] == b'.') {
+        pos += 1;
+    }
+    // An exponent needs at least one digit, otherwise the `e` belongs to the unit
+    if pos < bytes.len() && (bytes[pos] == b'e' || bytes[pos] == b'E') {
+        let mut exp_pos = pos + 1;
+        if exp_pos < bytes.len() && (bytes[exp_pos] == b'+' || bytes[exp_pos] == b'-') {
+            exp_pos += 1;
+        }
+        if exp_pos < bytes.len() && bytes[exp_pos].is_ascii_digit() {
+            pos = exp_pos;
+            while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                pos += 1;
+            }
+        }
+    }
+    pos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_spice_number;
+
+    fn assert_close(text: &str, expected: f64) {
+        let value = parse_spice_number(text).unwrap();
+        assert!((value - expected).abs() <= expected.abs() * 1e-12, "{}: {} != {}", text, value, expected);
+    }
+
+    #[test]
+    fn plain_numbers() {
+        assert_close("1", 1.0);
+        assert_close("-2.5", -2.5);
+        assert_close("+.5", 0.5);
+        assert_close("1e-3", 1e-3);
+        assert_close("1.5E+3", 1.5e3);
+    }
+
+    #[test]
+    fn suffixes() {
+        assert_close("1f", 1e-15);
+        assert_close("1p", 1e-12);
+        assert_close("1n", 1e-9);
+        assert_close("4.7u", 4.7e-6);
+        assert_close("4.7\u{b5}", 4.7e-6);
+        assert_close("4.7\u{3bc}", 4.7e-6);
+        assert_close("1m", 1e-3);
+        assert_close("2.2k", 2.2e3);
+        assert_close("10meg", 10e6);
+        assert_close("1g", 1e9);
+        assert_close("1t", 1e12);
+        assert_close("10mil", 254e-6);
+    }
+
+    #[test]
+    fn suffixes_are_not_case_sensitive() {
+        assert_close("10MEG", 10e6);
+        assert_close("10Meg", 10e6);
+        assert_close("1M", 1e-3);
+        assert_close("2.2K", 2.2e3);
+    }
+
+    #[test]
+    fn units_after_the_suffix() {
+        assert_close("100pF", 100e-12);
+        assert_close("10megHz", 10e6);
+        assert_close("1mV", 1e-3);
+        assert_close("5V", 5.0);
+        assert_close("3Hz", 3.0);
+    }
+
+    #[test]
+    fn exponent_or_unit() {
+        // An `e` without digits belongs to the unit
+        assert_close("1e", 1.0);
+        assert_close("2eV", 2.0);
+        assert_close("1e-", 1.0);
+        assert_close("1e3k", 1e6);
+    }
+
+    #[test]
+    fn not_a_number() {
+        assert_eq!(parse_spice_number(""), None);
+        assert_eq!(parse_spice_number("k"), None);
+        assert_eq!(parse_spice_number("abc"), None);
+        assert_eq!(parse_spice_number("-"), None);
+    }
+}
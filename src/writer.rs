@@ -0,0 +1,215 @@
+//! A writer for `.raw`-Files
+//!
+//! A `RawWriter` collects the values of several variables (traces) and writes them as a binary
+//! or an ASCII `.raw`-File, which can be opened with the waveform viewer of LTspice and read
+//! again with `SteppedSimulation`. The traces are either taken from a simulation or added one
+//! by one.
+//!
+//! # Examples
+//! ```no_run
+//! # use std::fs::File;
+//! # use ltspice_parse::header::Flags;
+//! # use ltspice_parse::results::VariableType;
+//! # use ltspice_parse::writer::RawWriter;
+//! let mut flags = Flags::default();
+//! flags.real = true;
+//! flags.forward = true;
+//! let mut writer = RawWriter::new("* test.asc","Transient Analysis",flags);
+//! writer.add_trace("time",VariableType::Time,vec![0.0,1e-3,2e-3],Vec::new()).unwrap();
+//! writer.add_trace("V(out)",VariableType::Voltage,vec![0.0,0.5,1.0],Vec::new()).unwrap();
+//! writer.write_binary(&mut File::create("test.raw").unwrap()).unwrap();
+//! ```
+use std::io::Write;
+
+use decode;
+use error::ParseError;
+use header::{Flags, PlotKind, RawHeader, Simulator};
+use results::{SimulationVariable, VariableType};
+use SteppedSimulation;
+
+
+/// Collects traces and writes them to a `.raw`-File
+#[derive(Debug)]
+pub struct RawWriter {
+    header: RawHeader,
+    variables: Vec<SimulationVariable>,
+    reals: Vec<Vec<f64>>,
+    imags: Vec<Vec<f64>>
+}
+
+impl RawWriter {
+    /// Creates a writer without any traces.
+    ///
+    /// The first trace is the sweep variable (e.g. the time or the frequency), except for
+    /// `.op` and `.tf` plots. If `flags.complex` is set, the imaginary parts are written as well.
+    pub fn new(title: &str, plot_name: &str, flags: Flags) -> Self {
+        RawWriter {
+            header: RawHeader {
+                title: title.to_owned(),
+                date: None,
+                plot_name: plot_name.to_owned(),
+                plot_kind: PlotKind::from_plot_name(plot_name),
+                flags: flags,
+                nr_of_variables: 0,
+                nr_of_points: 0,
+                offset: 0.0,
+                command: String::from("rust_ltspice_parser"),
+                simulator: Simulator::Unknown,
+                ltspice_version: None,
+                big_endian: false,
+                backannotations: Vec::new()
+            },
+            variables: Vec::new(),
+            reals: Vec::new(),
+            imags: Vec::new()
+        }
+    }
+
+    /// Creates a writer, which contains all steps of a simulation. The description of the
+    /// simulation (title, date, flags, ...) is kept.
+    pub fn from_simulation(simulation: &SteppedSimulation) -> Result<Self,ParseError> {
+        let mut header = simulation.header.clone();
+        // The values are always written little endian
+        header.big_endian = false;
+        let mut writer = RawWriter {
+            header: header,
+            variables: Vec::new(),
+            reals: Vec::new(),
+            imags: Vec::new()
+        };
+        for (var_index,variable) in simulation.variables.iter().enumerate() {
+            let (mut reals,imags) = simulation.columns.with_column(&simulation.header,var_index,|reals,imags| (reals.to_vec(),imags.to_vec()))?;
+            if var_index == 0 {
+                // The marks of LTspice are kept
+                for point in &simulation.compressed_points {
+                    reals[*point] = -reals[*point];
+                }
+            }
+            // The type is written like in the file, e.g. `subckt_current` instead of `device_current`
+            writer.push_trace(&variable.name,variable.var_type,&variable.type_name,reals,imags)?;
+        }
+        Ok(writer)
+    }
+
+    /// Adds the values of a variable. All traces need the same number of values.
+    ///
+    /// The imaginary parts may be empty, then they are zero.
+    pub fn add_trace(&mut self, name: &str, var_type: VariableType, reals: Vec<f64>, imags: Vec<f64>) -> Result<(),ParseError> {
+        self.push_trace(name,var_type,var_type.type_name(),reals,imags)
+    }
+
+    /// Adds the values of a variable, whose type is written as `type_name`
+    fn push_trace(&mut self, name: &str, var_type: VariableType, type_name: &str, reals: Vec<f64>, imags: Vec<f64>) -> Result<(),ParseError> {
+        let imags = if imags.is_empty() { vec![0.0;reals.len()] } else { imags };
+        if !self.variables.is_empty() && reals.len() != self.header.nr_of_points {
+            return Err(ParseError::PointCountMismatch {
+                expected: self.header.nr_of_points,
+                found: reals.len()
+            });
+        }
+        if imags.len() != reals.len() {
+            return Err(ParseError::PointCountMismatch {
+                expected: reals.len(),
+                found: imags.len()
+            });
+        }
+        self.header.nr_of_points = reals.len();
+        self.variables.push(SimulationVariable {
+            id: self.variables.len() as u16,
+            name: name.to_owned(),
+            var_type: var_type,
+            type_name: type_name.to_owned()
+        });
+        self.reals.push(reals);
+        self.imags.push(imags);
+        self.header.nr_of_variables = self.variables.len();
+        Ok(())
+    }
+
+    /// Returns the description, which is written in front of the values
+    pub fn header(&self) -> &RawHeader {
+        &self.header
+    }
+
+    /// Writes the file with binary values.
+    ///
+    /// The sizes of the values are the same as in files written by LTspice (see
+    /// `LTSpice_RAW_File_Info.md`). If the flag `fastaccess` is set, the values are written
+    /// variable by variable.
+    pub fn write_binary<W: Write>(&self, out: &mut W) -> Result<(),ParseError> {
+        out.write_all(self.description("Binary",self.header.flags).as_bytes())?;
+        let mut data = Vec::with_capacity(decode::point_size(&self.header) * self.header.nr_of_points);
+        if self.header.flags.fastaccess {
+            for var_index in 0..self.variables.len() {
+                for point in 0..self.header.nr_of_points {
+                    self.write_sample(&mut data,var_index,point);
+                }
+            }
+        } else {
+            for point in 0..self.header.nr_of_points {
+                for var_index in 0..self.variables.len() {
+                    self.write_sample(&mut data,var_index,point);
+                }
+            }
+        }
+        out.write_all(&data)?;
+        Ok(())
+    }
+
+    /// Writes the file with ASCII values. Every point starts with its index, complex values
+    /// are written as `real,imaginary`.
+    pub fn write_ascii<W: Write>(&self, out: &mut W) -> Result<(),ParseError> {
+        let mut flags = self.header.flags;
+        flags.fastaccess = false;
+        let mut text = self.description("Values",flags);
+        for point in 0..self.header.nr_of_points {
+            for var_index in 0..self.variables.len() {
+                if var_index == 0 {
+                    text.push_str(&point.to_string());
+                }
+                if self.header.flags.complex {
+                    text.push_str(&format!("\t{:.16e},{:.16e}\n",self.reals[var_index][point],self.imags[var_index][point]));
+                } else {
+                    text.push_str(&format!("\t{:.16e}\n",self.reals[var_index][point]));
+                }
+            }
+        }
+        out.write_all(text.as_bytes())?;
+        Ok(())
+    }
+
+    /// Creates the ASCII description, which ends with the given marker ("Binary" or "Values")
+    fn description(&self, marker: &str, flags: Flags) -> String {
+        let mut text = format!("Title: {}\n",self.header.title);
+        if let Some(date) = self.header.date {
+            text.push_str(&format!("Date: {}\n",date));
+        }
+        text.push_str(&format!("Plotname: {}\n",self.header.plot_name));
+        text.push_str(&format!("Flags: {}\n",flags));
+        text.push_str(&format!("No. Variables: {}\n",self.variables.len()));
+        text.push_str(&format!("No. Points: {}\n",self.header.nr_of_points));
+        text.push_str(&format!("Offset: {:.16e}\n",self.header.offset));
+        text.push_str(&format!("Command: {}\n",self.header.command));
+        for backannotation in &self.header.backannotations {
+            text.push_str(&format!("Backannotation: {}\n",backannotation));
+        }
+        text.push_str("Variables:\n");
+        for variable in &self.variables {
+            text.push_str(&format!("\t{}\t{}\t{}\n",variable.id,variable.name,variable.type_name));
+        }
+        text.push_str(&format!("{}:\n",marker));
+        text
+    }
+
+    /// Appends one value to the binary data
+    fn write_sample(&self, data: &mut Vec<u8>, var_index: usize, point: usize) {
+        match decode::sample_size(&self.header,var_index) {
+            16 => {
+                data.extend_from_slice(&self.reals[var_index][point].to_le_bytes());
+                data.extend_from_slice(&self.imags[var_index][point].to_le_bytes());
+            },
+            8 => data.extend_from_slice(&self.reals[var_index][point].to_le_bytes()),
+            _ => data.extend_from_slice(&(self.reals[var_index][point] as f32).to_le_bytes())
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Errors of lazily opened simulations, whose values are read on the first access
+extern crate ltspice_parse;
+
+use std::env;
+use std::fs::{self, File};
+use std::path::PathBuf;
+
+use ltspice_parse::SteppedSimulation;
+use ltspice_parse::error::ParseError;
+use ltspice_parse::header::Flags;
+use ltspice_parse::results::{SteppingVariable, VariableType};
+use ltspice_parse::writer::RawWriter;
+
+/// Returns a path in the temporary directory, which is unique for every test
+fn temp_path(name: &str) -> PathBuf {
+    let mut path = env::temp_dir();
+    path.push(format!("ltspice_parse_{}_{}.raw", name, std::process::id()));
+    path
+}
+
+#[test]
+fn errors_of_truncated_files_are_returned() {
+    let mut flags = Flags::default();
+    flags.real = true;
+    flags.forward = true;
+    let mut writer = RawWriter::new("* test.asc", "Transient Analysis", flags);
+    writer.add_trace("time", VariableType::Time, vec![0.0, 1e-3, 2e-3, 3e-3], Vec::new()).unwrap();
+    writer.add_trace("V(out)", VariableType::Voltage, vec![0.0, 0.5, 1.0, 1.5], Vec::new()).unwrap();
+    let path = temp_path("lazy_truncated");
+    writer.write_binary(&mut File::create(&path).unwrap()).unwrap();
+
+    let simulation = SteppedSimulation::open_lazy(&path).unwrap();
+    // The file is cut after the simulation was opened
+    let bytes = fs::read(&path).unwrap();
+    fs::write(&path, &bytes[..bytes.len() - 6]).unwrap();
+    let step = simulation.available_steps()[0].clone();
+    let out = &simulation.available_variables()[1];
+    let values = simulation.try_get_values_for_variable_at(&step, out);
+    let all_values = simulation.try_get_values_at(&step).map(|values| values.len());
+    let scalars = simulation.try_get_scalars_at(&step).map(|scalars| scalars.len());
+    let sub_blocks = simulation.try_get_sub_blocks_at(&step).map(|blocks| blocks.len());
+    fs::remove_file(&path).unwrap();
+
+    match values {
+        Err(ParseError::TruncatedBinary { .. }) => {},
+        other => panic!("unexpected result: {:?}", other.map(|values| values.len()))
+    }
+    assert!(all_values.is_err());
+    assert!(scalars.is_err());
+    assert!(sub_blocks.is_err());
+    assert!(simulation.get_values_at(&step).is_empty());
+    assert!(simulation.get_values_for_variable_at(&step, out).is_none());
+
+    let unknown = vec![SteppingVariable { name: String::from("r"), value: 1.0 }];
+    match simulation.try_get_values_at(&unknown) {
+        Err(ParseError::MissingStep(step)) => assert_eq!(step, unknown),
+        other => panic!("unexpected result: {:?}", other.map(|values| values.len()))
+    }
+}
@@ -0,0 +1,100 @@
+//! `.raw`-Files with several plots, like ngspice writes them
+extern crate ltspice_parse;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+
+use ltspice_parse::{RawFile, SteppedSimulation};
+use ltspice_parse::header::{PlotKind, Simulator};
+use ltspice_parse::results::DataType;
+
+/// Returns a path in the temporary directory, which is unique for every test
+fn temp_path(name: &str) -> PathBuf {
+    let mut path = env::temp_dir();
+    path.push(format!("ltspice_parse_{}_{}.raw", name, std::process::id()));
+    path
+}
+
+/// Appends a plot of ngspice with the given variables and doubles
+fn push_plot(buf: &mut Vec<u8>, plot_name: &str, flags: &str, variables: &[(&str, &str)], nr_of_points: usize, values: &[f64], big_endian: bool) {
+    let mut text = format!("Title: * ngspice test\nDate: Sat Oct 17 10:00:00  2026\nPlotname: {}\nFlags: {}\n\
+                            No. Variables: {}\nNo. Points: {}\nCommand: version 36\nVariables:\n",
+                           plot_name, flags, variables.len(), nr_of_points);
+    for (ct, &(name, type_name)) in variables.iter().enumerate() {
+        text.push_str(&format!("\t{}\t{}\t{}\n", ct, name, type_name));
+    }
+    text.push_str("Binary:\n");
+    buf.extend_from_slice(text.as_bytes());
+    for value in values {
+        if big_endian {
+            buf.extend_from_slice(&value.to_be_bytes());
+        } else {
+            buf.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+/// An operating point followed by an AC analysis with three frequencies
+fn op_and_ac(big_endian: bool) -> Vec<u8> {
+    let mut buf = Vec::new();
+    push_plot(&mut buf, "Operating Point", "real", &[("v(out)", "voltage"), ("i(v1)", "current")], 1, &[1.5, -2e-3], big_endian);
+    push_plot(&mut buf, "AC Analysis", "complex", &[("frequency", "frequency"), ("v(out)", "voltage")], 3,
+              &[10.0, 0.0, 1.0, -0.1, 100.0, 0.0, 0.5, -0.5, 1e3, 0.0, 0.1, -0.3], big_endian);
+    buf
+}
+
+fn read_plots(name: &str, buf: &[u8]) -> (RawFile, SteppedSimulation) {
+    let path = temp_path(name);
+    File::create(&path).unwrap().write_all(buf).unwrap();
+    let file = RawFile::from_file(&path).unwrap();
+    let first = SteppedSimulation::from_raw_file(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    (file, first)
+}
+
+#[test]
+fn plots_are_split() {
+    for &big_endian in &[false, true] {
+        let (file, first) = read_plots(if big_endian { "plots_big_endian" } else { "plots_little_endian" }, &op_and_ac(big_endian));
+        assert_eq!(file.plots().len(), 2);
+        assert!(file.plot(2).is_none());
+
+        let op = file.plot(0).unwrap();
+        assert_eq!(op.header().plot_kind, PlotKind::OperatingPoint);
+        assert_eq!(op.header().simulator, Simulator::Ngspice);
+        assert_eq!(op.header().big_endian, big_endian);
+        let step = &op.available_steps()[0];
+        assert_eq!(op.get_scalar_for_name(step, "i(v1)"), Some(-2e-3));
+
+        let ac = file.plot_for_name("AC Analysis").unwrap();
+        assert_eq!(ac.header().plot_kind, PlotKind::Ac);
+        assert_eq!(ac.header().big_endian, big_endian);
+        assert_eq!(ac.available_variables().len(), 2);
+        let step = &ac.available_steps()[0];
+        let out = ac.get_values_for_variable_at(step, &ac.available_variables()[1]).unwrap();
+        assert_eq!(out.get_data(&DataType::Real), vec![1.0, 0.5, 0.1]);
+        assert_eq!(out.get_data(&DataType::Imaginary), vec![-0.1, -0.5, -0.3]);
+
+        // Only the first plot is read by `from_raw_file`
+        assert_eq!(first.header().plot_kind, PlotKind::OperatingPoint);
+        assert_eq!(first.get_scalar_for_name(&first.available_steps()[0], "v(out)"), Some(1.5));
+    }
+}
+
+#[test]
+fn line_breaks_after_the_last_plot() {
+    let mut buf = op_and_ac(false);
+    buf.extend_from_slice(b"\n\n");
+    let (file, _) = read_plots("plots_line_breaks", &buf);
+    assert_eq!(file.plots().len(), 2);
+
+    // A cut plot is an error
+    let buf = op_and_ac(false);
+    let path = temp_path("plots_truncated");
+    File::create(&path).unwrap().write_all(&buf[..buf.len() - 8]).unwrap();
+    let file = RawFile::from_file(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(file.is_err());
+}
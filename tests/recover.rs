@@ -0,0 +1,124 @@
+//! Recovery of `.raw`-Files, which end in the middle of a point
+extern crate ltspice_parse;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+
+use ltspice_parse::SteppedSimulation;
+use ltspice_parse::header::Flags;
+use ltspice_parse::results::{DataType, Truncation, VariableType};
+use ltspice_parse::writer::RawWriter;
+
+/// Returns a path in the temporary directory, which is unique for every test
+fn temp_path(name: &str) -> PathBuf {
+    let mut path = env::temp_dir();
+    path.push(format!("ltspice_parse_{}_{}.raw", name, std::process::id()));
+    path
+}
+
+fn real_flags() -> Flags {
+    let mut flags = Flags::default();
+    flags.real = true;
+    flags.forward = true;
+    flags
+}
+
+/// Two transient steps with four and three points
+fn transient_writer() -> RawWriter {
+    let mut writer = RawWriter::new("* test.asc", "Transient Analysis", real_flags());
+    writer.add_trace("time", VariableType::Time, vec![0.0, 1e-4, 2.5e-4, 1e-3, 0.0, 3e-4, 1e-3], Vec::new()).unwrap();
+    writer.add_trace("V(out)", VariableType::Voltage, vec![0.0, 0.5, 0.75, 1.0, 0.0, 0.25, 2.0], Vec::new()).unwrap();
+    writer
+}
+
+/// Writes the first `len` bytes of the file (and the `.log`-File, if given) and recovers it
+fn recover(name: &str, bytes: &[u8], len: usize, log: Option<&str>) -> SteppedSimulation {
+    let path = temp_path(name);
+    let path_log = path.with_extension("log");
+    File::create(&path).unwrap().write_all(&bytes[..len]).unwrap();
+    if let Some(log) = log {
+        File::create(&path_log).unwrap().write_all(log.as_bytes()).unwrap();
+    }
+    let simulation = SteppedSimulation::recover_raw_file(&path);
+    assert!(SteppedSimulation::from_raw_file(&path).is_err());
+    fs::remove_file(&path).unwrap();
+    if log.is_some() {
+        fs::remove_file(&path_log).unwrap();
+    }
+    simulation.unwrap()
+}
+
+fn times(simulation: &SteppedSimulation) -> Vec<Vec<f64>> {
+    let time = &simulation.available_variables()[0];
+    simulation.available_steps().iter()
+        .map(|step| simulation.get_values_for_variable_at(step, time).unwrap().get_data(&DataType::Real))
+        .collect()
+}
+
+#[test]
+fn truncated_binary() {
+    let mut bytes = Vec::new();
+    transient_writer().write_binary(&mut bytes).unwrap();
+    // A point consists of the time (8 bytes) and the voltage (4 bytes). The last point is cut.
+    let simulation = recover("truncated_binary", &bytes, bytes.len() - 5, None);
+    assert_eq!(simulation.truncation(), Some(Truncation { expected: 7, found: 6 }));
+    assert_eq!(simulation.header().nr_of_points, 7);
+    assert_eq!(times(&simulation), vec![vec![0.0, 1e-4, 2.5e-4, 1e-3], vec![0.0, 3e-4]]);
+    let steps = simulation.available_steps();
+    assert!(simulation.is_step_complete(&steps[0]));
+    assert!(!simulation.is_step_complete(&steps[1]));
+}
+
+#[test]
+fn truncated_ascii() {
+    let mut bytes = Vec::new();
+    transient_writer().write_ascii(&mut bytes).unwrap();
+    // The voltage of the last point is missing
+    let text = String::from_utf8(bytes).unwrap();
+    let len = text.trim_end().rfind('\n').unwrap();
+    let simulation = recover("truncated_ascii", text.as_bytes(), len, None);
+    assert_eq!(simulation.truncation(), Some(Truncation { expected: 7, found: 6 }));
+    assert_eq!(times(&simulation), vec![vec![0.0, 1e-4, 2.5e-4, 1e-3], vec![0.0, 3e-4]]);
+}
+
+#[test]
+fn steps_after_the_abort_are_dropped() {
+    let mut bytes = Vec::new();
+    transient_writer().write_binary(&mut bytes).unwrap();
+    // The file ends after the second point of the first step
+    let log = ".step r=1\n.step r=2\n";
+    let simulation = recover("steps_after_the_abort", &bytes, bytes.len() - 5 * 12, Some(log));
+    assert_eq!(simulation.available_steps().len(), 1);
+    assert_eq!(simulation.available_steps()[0][0].value, 1.0);
+    assert_eq!(times(&simulation), vec![vec![0.0, 1e-4]]);
+    assert!(!simulation.is_step_complete(&simulation.available_steps()[0]));
+}
+
+#[test]
+fn truncated_nested_sweep() {
+    // .dc V1 0 1 1 V2 0 2 1 with two steps: Every step has three blocks of two points
+    let sweep: Vec<f64> = (0..6).flat_map(|_| vec![0.0, 1.0]).collect();
+    let out: Vec<f64> = (0..12).map(|ct| ct as f64).collect();
+    let mut flags = real_flags();
+    flags.stepped = true;
+    let mut writer = RawWriter::new("* test.asc", "DC transfer characteristic", flags);
+    writer.add_trace("V1", VariableType::Voltage, sweep, Vec::new()).unwrap();
+    writer.add_trace("V(out)", VariableType::Voltage, out, Vec::new()).unwrap();
+    let mut bytes = Vec::new();
+    writer.write_binary(&mut bytes).unwrap();
+
+    // Only eight points are complete, so the first block of the second step was written
+    let log = ".step r=1\n.step r=2\n";
+    let simulation = recover("truncated_nested_sweep", &bytes, bytes.len() - 4 * 12 + 3, Some(log));
+    assert_eq!(simulation.truncation(), Some(Truncation { expected: 12, found: 8 }));
+    let steps = simulation.available_steps();
+    assert_eq!(steps.len(), 2);
+    assert_eq!(simulation.get_sub_blocks_at(&steps[0]).len(), 3);
+    assert_eq!(simulation.get_sub_blocks_at(&steps[1]).len(), 1);
+    let out = &simulation.available_variables()[1];
+    assert_eq!(simulation.get_values_for_variable_at(&steps[1], out).unwrap().get_data(&DataType::Real), vec![6.0, 7.0]);
+    assert!(simulation.is_step_complete(&steps[0]));
+    assert!(!simulation.is_step_complete(&steps[1]));
+}
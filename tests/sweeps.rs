@@ -0,0 +1,106 @@
+//! Steps and nested blocks of `.dc` sweeps with two sources
+extern crate ltspice_parse;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+
+use ltspice_parse::SteppedSimulation;
+use ltspice_parse::error::ParseError;
+use ltspice_parse::header::Flags;
+use ltspice_parse::results::{DataType, VariableResult, VariableType};
+use ltspice_parse::writer::RawWriter;
+
+/// Returns a path in the temporary directory, which is unique for every test
+fn temp_path(name: &str) -> PathBuf {
+    let mut path = env::temp_dir();
+    path.push(format!("ltspice_parse_{}_{}.raw", name, std::process::id()));
+    path
+}
+
+/// Writes a `.dc` sweep of `V1` and reads it again. With `log` a `.log`-File with these
+/// lines is written next to the `.raw`-File.
+fn read_dc_sweep(name: &str, sweep: Vec<f64>, log: Option<&str>) -> Result<SteppedSimulation, ParseError> {
+    let mut flags = Flags::default();
+    flags.real = true;
+    flags.forward = true;
+    flags.stepped = log.is_some();
+    let mut writer = RawWriter::new("* test.asc", "DC transfer characteristic", flags);
+    let out: Vec<f64> = sweep.iter().enumerate().map(|(ct, v1)| v1 * 2.0 + ct as f64).collect();
+    writer.add_trace("V1", VariableType::Voltage, sweep, Vec::new()).unwrap();
+    writer.add_trace("V(out)", VariableType::Voltage, out, Vec::new()).unwrap();
+
+    let path = temp_path(name);
+    let path_log = path.with_extension("log");
+    writer.write_binary(&mut File::create(&path).unwrap()).unwrap();
+    if let Some(log) = log {
+        File::create(&path_log).unwrap().write_all(log.as_bytes()).unwrap();
+    }
+    let simulation = SteppedSimulation::from_raw_file(&path);
+    fs::remove_file(&path).unwrap();
+    if log.is_some() {
+        fs::remove_file(&path_log).unwrap();
+    }
+    simulation
+}
+
+/// Repeats the values of the first source for every block
+fn blocks(values: &[f64], nr_of_blocks: usize) -> Vec<f64> {
+    (0..nr_of_blocks).flat_map(|_| values.iter().cloned()).collect()
+}
+
+fn reals(results: &[VariableResult]) -> Vec<Vec<f64>> {
+    results.iter().map(|result| result.get_data(&DataType::Real)).collect()
+}
+
+#[test]
+fn nested_sweep_without_step() {
+    // .dc V1 0 2 1 V2 0 1 1
+    let simulation = read_dc_sweep("nested_without_step", blocks(&[0.0, 1.0, 2.0], 2), None).unwrap();
+    let steps = simulation.available_steps();
+    assert_eq!(steps.len(), 1);
+    assert!(steps[0].is_empty());
+    assert_eq!(simulation.get_values_at(&steps[0])[0].len(), 6);
+
+    let sub_blocks = simulation.get_sub_blocks_at(&steps[0]);
+    assert_eq!(sub_blocks.len(), 2);
+    assert_eq!(reals(&sub_blocks[0]), vec![vec![0.0, 1.0, 2.0], vec![0.0, 3.0, 6.0]]);
+    assert_eq!(reals(&sub_blocks[1]), vec![vec![0.0, 1.0, 2.0], vec![3.0, 6.0, 9.0]]);
+
+    let out = &simulation.available_variables()[1];
+    let out_blocks = simulation.get_sub_blocks_for_variable_at(&steps[0], out);
+    assert_eq!(reals(&out_blocks), vec![vec![0.0, 3.0, 6.0], vec![3.0, 6.0, 9.0]]);
+}
+
+#[test]
+fn nested_sweep_with_step() {
+    // .dc V1 0 2 1 V2 0 1 1 with .step param r list 1 2
+    let log = ".step r=1\n.step r=2\n";
+    let simulation = read_dc_sweep("nested_with_step", blocks(&[0.0, 1.0, 2.0], 4), Some(log)).unwrap();
+    let steps = simulation.available_steps();
+    assert_eq!(steps.len(), 2);
+    assert_eq!(steps[1][0].value, 2.0);
+    assert_eq!(simulation.get_values_at(&steps[1])[1].get_data(&DataType::Real), vec![6.0, 9.0, 12.0, 9.0, 12.0, 15.0]);
+
+    let sub_blocks = simulation.get_sub_blocks_at(&steps[1]);
+    assert_eq!(sub_blocks.len(), 2);
+    assert_eq!(reals(&sub_blocks[0]), vec![vec![0.0, 1.0, 2.0], vec![6.0, 9.0, 12.0]]);
+    assert_eq!(reals(&sub_blocks[1]), vec![vec![0.0, 1.0, 2.0], vec![9.0, 12.0, 15.0]]);
+}
+
+#[test]
+fn blocks_must_fit_to_the_steps() {
+    let log = ".step r=1\n.step r=2\n";
+    match read_dc_sweep("three_blocks", blocks(&[0.0, 1.0, 2.0], 3), Some(log)) {
+        Err(ParseError::StepCountMismatch { blocks, steps }) => assert_eq!((blocks, steps), (3, 2)),
+        other => panic!("unexpected result: {:?}", other.map(|simulation| simulation.available_steps().len()))
+    }
+
+    // Four blocks fit to two steps, but the second step has blocks of different lengths
+    let sweep = vec![0.0, 1.0, 2.0, 0.0, 1.0, 2.0, 0.0, 1.0, 0.0, 1.0, 2.0];
+    match read_dc_sweep("different_blocks", sweep, Some(log)) {
+        Err(ParseError::StepCountMismatch { blocks, steps }) => assert_eq!((blocks, steps), (4, 2)),
+        other => panic!("unexpected result: {:?}", other.map(|simulation| simulation.available_steps().len()))
+    }
+}
@@ -0,0 +1,252 @@
+//! Round trips of `.raw`-Files through the writer and the parser
+extern crate ltspice_parse;
+
+use std::env;
+use std::fs::{self, File};
+use std::path::PathBuf;
+
+use ltspice_parse::SteppedSimulation;
+use ltspice_parse::header::Flags;
+use ltspice_parse::results::{DataType, VariableType};
+use ltspice_parse::writer::RawWriter;
+
+/// A trace with its name, type, real and imaginary parts
+type Trace = (&'static str, VariableType, Vec<f64>, Vec<f64>);
+
+/// Returns a path in the temporary directory, which is unique for every test
+fn temp_path(name: &str) -> PathBuf {
+    let mut path = env::temp_dir();
+    path.push(format!("ltspice_parse_{}_{}.raw", name, std::process::id()));
+    path
+}
+
+fn writer_for(plot_name: &str, flags: Flags, traces: &[Trace]) -> RawWriter {
+    let mut writer = RawWriter::new("* test.asc", plot_name, flags);
+    for &(name, var_type, ref reals, ref imags) in traces {
+        writer.add_trace(name, var_type, reals.clone(), imags.clone()).unwrap();
+    }
+    writer
+}
+
+/// Writes the traces, reads the file again and returns the simulation
+fn round_trip(name: &str, plot_name: &str, flags: Flags, traces: &[Trace], ascii: bool) -> SteppedSimulation {
+    let path = temp_path(name);
+    {
+        let writer = writer_for(plot_name, flags, traces);
+        let mut file = File::create(&path).unwrap();
+        if ascii {
+            writer.write_ascii(&mut file).unwrap();
+        } else {
+            writer.write_binary(&mut file).unwrap();
+        }
+    }
+    let simulation = SteppedSimulation::from_raw_file(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    simulation
+}
+
+/// Returns the values of every variable for all steps together
+fn all_values(simulation: &SteppedSimulation, data_type: DataType) -> Vec<Vec<f64>> {
+    simulation.available_variables().iter().map(|var| {
+        simulation.available_steps().iter()
+            .flat_map(|step| simulation.get_values_for_variable_at(step, var).unwrap().get_data(&data_type))
+            .collect()
+    }).collect()
+}
+
+fn assert_traces(simulation: &SteppedSimulation, traces: &[Trace]) {
+    let names: Vec<&str> = simulation.available_variables().iter().map(|var| var.name.as_str()).collect();
+    let expected_names: Vec<&str> = traces.iter().map(|trace| trace.0).collect();
+    assert_eq!(names, expected_names);
+    for (var, trace) in simulation.available_variables().iter().zip(traces) {
+        assert_eq!(var.var_type, trace.1);
+    }
+    let reals: Vec<Vec<f64>> = traces.iter().map(|trace| trace.2.clone()).collect();
+    assert_eq!(all_values(simulation, DataType::Real), reals);
+    if simulation.header().flags.complex {
+        let imags: Vec<Vec<f64>> = traces.iter().map(|trace| trace.3.clone()).collect();
+        assert_eq!(all_values(simulation, DataType::Imaginary), imags);
+    }
+}
+
+fn real_flags() -> Flags {
+    let mut flags = Flags::default();
+    flags.real = true;
+    flags.forward = true;
+    flags
+}
+
+fn complex_flags() -> Flags {
+    let mut flags = Flags::default();
+    flags.complex = true;
+    flags.forward = true;
+    flags.log = true;
+    flags
+}
+
+/// Two transient steps of different length. The voltages can be stored as floats without loss.
+fn transient_traces() -> Vec<Trace> {
+    vec![
+        ("time", VariableType::Time, vec![0.0, 1e-4, 2.5e-4, 1e-3, 0.0, 3e-4, 1e-3], Vec::new()),
+        ("V(out)", VariableType::Voltage, vec![0.0, 0.5, 0.75, 1.0, 0.0, 0.25, 2.0], Vec::new()),
+        ("I(R1)", VariableType::Current, vec![0.0, -0.125, 0.5, 1.5, 0.0, 0.0625, -4.0], Vec::new())
+    ]
+}
+
+/// Two AC steps with complex values
+fn ac_traces() -> Vec<Trace> {
+    vec![
+        ("frequency", VariableType::Frequency, vec![10.0, 100.0, 1000.0, 10.0, 100.0, 1000.0], vec![0.0; 6]),
+        ("V(out)", VariableType::Voltage, vec![1.0, 0.9, 0.1, 0.5, 0.45, 0.05], vec![-0.01, -0.1, -0.7, 0.0, -0.2, -0.3])
+    ]
+}
+
+#[test]
+fn real_binary() {
+    let traces = transient_traces();
+    let simulation = round_trip("real_binary", "Transient Analysis", real_flags(), &traces, false);
+    assert_eq!(simulation.available_steps().len(), 2);
+    assert_traces(&simulation, &traces);
+}
+
+#[test]
+fn real_ascii() {
+    let traces = transient_traces();
+    let simulation = round_trip("real_ascii", "Transient Analysis", real_flags(), &traces, true);
+    assert_eq!(simulation.available_steps().len(), 2);
+    assert_traces(&simulation, &traces);
+}
+
+#[test]
+fn real_fastaccess() {
+    let traces = transient_traces();
+    let mut flags = real_flags();
+    flags.fastaccess = true;
+    let simulation = round_trip("real_fastaccess", "Transient Analysis", flags, &traces, false);
+    assert!(simulation.header().flags.fastaccess);
+    assert_traces(&simulation, &traces);
+}
+
+#[test]
+fn real_double() {
+    // These values need all bits of a double
+    let traces: Vec<Trace> = vec![
+        ("time", VariableType::Time, vec![0.0, 1e-9, 2e-9], Vec::new()),
+        ("V(out)", VariableType::Voltage, vec![0.1, 1.0 / 3.0, 2.0f64.sqrt()], Vec::new())
+    ];
+    let mut flags = real_flags();
+    flags.double = true;
+    let simulation = round_trip("real_double", "Transient Analysis", flags, &traces, false);
+    assert!(simulation.header().flags.double);
+    assert_traces(&simulation, &traces);
+
+    flags.fastaccess = true;
+    let simulation = round_trip("real_double_fastaccess", "Transient Analysis", flags, &traces, false);
+    assert_traces(&simulation, &traces);
+}
+
+#[test]
+fn real_floats_lose_precision() {
+    // Without the flag `double` all variables but the time are stored as floats
+    let traces: Vec<Trace> = vec![
+        ("time", VariableType::Time, vec![0.0, 1e-9], Vec::new()),
+        ("V(out)", VariableType::Voltage, vec![0.1, 1.0 / 3.0], Vec::new())
+    ];
+    let simulation = round_trip("real_floats", "Transient Analysis", real_flags(), &traces, false);
+    let values = all_values(&simulation, DataType::Real);
+    assert_eq!(values[0], traces[0].2);
+    assert_eq!(values[1], vec![0.1f32 as f64, (1.0f32 / 3.0) as f64]);
+}
+
+#[test]
+fn complex_binary() {
+    let traces = ac_traces();
+    let simulation = round_trip("complex_binary", "AC Analysis", complex_flags(), &traces, false);
+    assert_eq!(simulation.available_steps().len(), 2);
+    assert_traces(&simulation, &traces);
+}
+
+#[test]
+fn complex_ascii() {
+    let traces = ac_traces();
+    let simulation = round_trip("complex_ascii", "AC Analysis", complex_flags(), &traces, true);
+    assert_eq!(simulation.available_steps().len(), 2);
+    assert_traces(&simulation, &traces);
+}
+
+#[test]
+fn complex_fastaccess() {
+    let traces = ac_traces();
+    let mut flags = complex_flags();
+    flags.fastaccess = true;
+    let simulation = round_trip("complex_fastaccess", "AC Analysis", flags, &traces, false);
+    assert_traces(&simulation, &traces);
+}
+
+#[test]
+fn single_variable_of_fastaccess_file() {
+    let traces = transient_traces();
+    let mut flags = real_flags();
+    flags.fastaccess = true;
+    let path = temp_path("single_variable");
+    writer_for("Transient Analysis", flags, &traces).write_binary(&mut File::create(&path).unwrap()).unwrap();
+    let (reals, imags) = SteppedSimulation::read_single_variable(&path, "I(R1)").unwrap();
+    let lazy = SteppedSimulation::open_lazy(&path).unwrap();
+    let lazy_values = all_values(&lazy, DataType::Real);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(reals, traces[2].2);
+    assert_eq!(imags, vec![0.0; reals.len()]);
+    assert_eq!(lazy_values, traces.iter().map(|trace| trace.2.clone()).collect::<Vec<_>>());
+}
+
+#[test]
+fn compressed_points_are_kept() {
+    let mut traces = transient_traces();
+    // LTspice marks points with a negative time
+    traces[0].2[2] = -traces[0].2[2];
+    let simulation = round_trip("compressed_points", "Transient Analysis", real_flags(), &traces, false);
+    let steps = simulation.available_steps();
+    assert_eq!(simulation.compressed_points(&steps[0]), vec![2]);
+    assert_eq!(all_values(&simulation, DataType::Real)[0][2], 2.5e-4);
+
+    // Writing the simulation again keeps the mark
+    let path = temp_path("compressed_points_again");
+    RawWriter::from_simulation(&simulation).unwrap().write_binary(&mut File::create(&path).unwrap()).unwrap();
+    let again = SteppedSimulation::from_raw_file(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(again.compressed_points(&again.available_steps()[0]), vec![2]);
+    assert_eq!(all_values(&again, DataType::Real), all_values(&simulation, DataType::Real));
+}
+
+#[test]
+fn from_simulation_keeps_the_description() {
+    let traces = ac_traces();
+    let simulation = round_trip("description", "AC Analysis", complex_flags(), &traces, false);
+    let writer = RawWriter::from_simulation(&simulation).unwrap();
+    assert_eq!(writer.header().title, "* test.asc");
+    assert_eq!(writer.header().plot_name, "AC Analysis");
+    assert_eq!(writer.header().flags, simulation.header().flags);
+
+    for &ascii in &[false, true] {
+        let path = temp_path(if ascii { "description_ascii" } else { "description_binary" });
+        {
+            let mut file = File::create(&path).unwrap();
+            if ascii {
+                writer.write_ascii(&mut file).unwrap();
+            } else {
+                writer.write_binary(&mut file).unwrap();
+            }
+        }
+        let again = SteppedSimulation::from_raw_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_traces(&again, &traces);
+    }
+}
+
+#[test]
+fn traces_need_the_same_length() {
+    let mut writer = RawWriter::new("* test.asc", "Transient Analysis", real_flags());
+    writer.add_trace("time", VariableType::Time, vec![0.0, 1.0], Vec::new()).unwrap();
+    assert!(writer.add_trace("V(out)", VariableType::Voltage, vec![0.0], Vec::new()).is_err());
+    assert!(writer.add_trace("V(in)", VariableType::Voltage, vec![0.0, 1.0], vec![0.0]).is_err());
+}